@@ -1,32 +1,389 @@
+use std::borrow::Cow;
 use std::fs;
-use anyhow::Result;
-use ripntear::i8085;
+use anyhow::{anyhow, Result};
+use ripntear::i8085::{self, trace, Cfg, GraphFormat, GraphWriter};
+use ripntear::i8085::memory::{BankMap, Selector};
+use ripntear::json;
+use ripntear::loader::{Format, Image};
+use ripntear::printer::generate_labels;
+use ripntear::{AddressWidth, BankedAddress, Column, DataFormat, Entry, HexStyle, Layout, MemoryImage, Printer, SymbolKind, Symbols, Syntax, Xrefs};
 use structopt::StructOpt;
+use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+/// What to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Listing,
+    /// Source AS will assemble back into the image
+    Source,
+    /// A JSON document, as described in `ripntear::json`
+    Json,
+    /// Just the document's lines, one JSON object per line of text
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s.to_lowercase().as_str() {
+            "listing" => Ok(OutputFormat::Listing),
+            "source" => Ok(OutputFormat::Source),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!("unknown format {:?} (expected listing, source, json or jsonl)", s)),
+        }
+    }
+}
 
 #[derive(Debug, StructOpt)]
 struct Opt {
-    #[structopt(name = "FILE", parse(from_os_str))]
-    file: PathBuf,
+    /// Image to disassemble: a flat binary, Intel HEX (.hex) or S-records (.s19 etc). Flat
+    /// binaries load at --base, or at ADDR (hex) when given as FILE@ADDR.
+    #[structopt(name = "FILE", required = true)]
+    files: Vec<String>,
+
+    /// What to write: a listing, source for the AS assembler, json or jsonl
+    #[structopt(long, default_value = "listing")]
+    format: OutputFormat,
 
+    /// Print just the code, without addresses or bytes
     #[structopt(short, long)]
     raw: bool,
+
+    /// Columns to print, comma-separated, from address, bytes, label, cycles, mnemonic,
+    /// operands and comment
+    #[structopt(long, use_delimiter = true)]
+    columns: Vec<Column>,
+
+    /// How to mark hex numbers: 0x, $ or h. Defaults to h for the zilog and tdl syntaxes,
+    /// and 0x otherwise
+    #[structopt(long)]
+    hex: Option<HexStyle>,
+
+    /// Upper-case hex digits
+    #[structopt(long)]
+    uppercase: bool,
+
+    /// Mnemonics to list in: raw, intel, zilog (or z80) or tdl (or m80). Source is always
+    /// written in Intel's.
+    #[structopt(long, default_value = "raw")]
+    syntax: Syntax,
+
+    /// Show T-states for each instruction
+    #[structopt(short, long)]
+    cycles: bool,
+
+    /// Processor to decode for: 8080, 8085 or 8085undoc
+    #[structopt(long, default_value = "8085undoc")]
+    cpu: i8085::Cpu,
+
+    /// Decode every byte in order instead of following control flow
+    #[structopt(long)]
+    linear: bool,
+
+    /// Symbol file to load (ours, an AS .lst or a MAME .cmt); may be repeated
+    #[structopt(short, long = "symbols", number_of_values = 1, parse(from_os_str))]
+    symbols: Vec<PathBuf>,
+
+    /// Write the loaded symbols, plus labels generated for branch targets, to this file
+    #[structopt(long, parse(from_os_str))]
+    export_symbols: Option<PathBuf>,
+
+    /// Additional address to trace from (hex); may be repeated
+    #[structopt(short, long = "entry", number_of_values = 1, parse(try_from_str = parse_addr))]
+    entries: Vec<u16>,
+
+    /// Address flat binaries are loaded at, after skipping (hex)
+    #[structopt(long, default_value = "0", parse(try_from_str = parse_addr))]
+    base: u16,
+
+    /// Bytes to skip at the start of each flat binary (hex)
+    #[structopt(long, default_value = "0", parse(try_from_str = parse_size))]
+    skip: usize,
+
+    /// Bytes of each flat binary to use after skipping (hex); defaults to the rest of it
+    #[structopt(long, parse(try_from_str = parse_size))]
+    length: Option<usize>,
+
+    /// Bank layout to trace through (pc8300), with each bank's contents at its offset in
+    /// the image
+    #[structopt(long)]
+    banks: Option<String>,
+
+    /// Port the bank selector is written through (hex)
+    #[structopt(long, parse(try_from_str = parse_port))]
+    bank_port: Option<u8>,
+
+    /// Address of a memory-mapped bank selector (hex)
+    #[structopt(long, parse(try_from_str = parse_addr))]
+    bank_latch: Option<u16>,
+
+    /// Print control-flow graphs for each function, as dot or mermaid, instead of a listing
+    #[structopt(long)]
+    graph: Option<GraphFormat>,
+
+    /// Only graph the function entered at this address (hex)
+    #[structopt(long, parse(try_from_str = parse_addr))]
+    function: Option<u16>,
+
+    /// Show calls out of each function in its graph
+    #[structopt(long)]
+    show_calls: bool,
+
+    /// Graph which functions call which, rather than each function's blocks
+    #[structopt(long)]
+    call_graph: bool,
+
+    /// Note what refers to each address, above its line
+    #[structopt(long)]
+    xrefs: bool,
+
+    /// Show data as little-endian words rather than bytes
+    #[structopt(long)]
+    words: bool,
+
+    /// First address to list (hex)
+    #[structopt(long, parse(try_from_str = parse_addr))]
+    start: Option<u16>,
+
+    /// Address to stop listing before (hex); 10000 lists to the end of memory
+    #[structopt(long, parse(try_from_str = parse_end))]
+    end: Option<usize>,
+}
+
+fn parse_size(s: &str) -> Result<usize> {
+    let digits = s.trim_start_matches("0x").trim_start_matches('$').trim_end_matches('h');
+    usize::from_str_radix(digits, 16).map_err(|e| anyhow!("bad number {:?}: {}", s, e))
+}
+
+fn parse_addr(s: &str) -> Result<u16> {
+    let n = parse_size(s)?;
+    if n > 0xffff {
+        return Err(anyhow!("address {:?} is past 0xffff", s));
+    }
+    Ok(n as u16)
+}
+
+/// An exclusive end address, which can be just past 0xffff.
+fn parse_end(s: &str) -> Result<usize> {
+    let n = parse_size(s)?;
+    if n > 0x10000 {
+        return Err(anyhow!("end {:?} is past 0x10000", s));
+    }
+    Ok(n)
+}
+
+fn parse_port(s: &str) -> Result<u8> {
+    let n = parse_size(s)?;
+    if n > 0xff {
+        return Err(anyhow!("port {:?} is past 0xff", s));
+    }
+    Ok(n as u8)
+}
+
+/// Decodes every readable byte from `start` on, in order.
+fn linear(mem: &MemoryImage, start: usize, cpu: i8085::Cpu) -> Vec<(BankedAddress, Entry<i8085::Instruction>)> {
+    let mut listing = Vec::new();
+    let mut i = start;
+    for region in mem.regions().iter().filter(|r| r.readable) {
+        i = i.max(region.base);
+        while i < region.base + region.data.len() {
+            match i8085::Instruction::decode_at(mem, i, cpu) {
+                Ok((cnt, inst)) => {
+                    listing.push((i.into(), Entry::Code(inst)));
+                    i += cnt;
+                }
+                Err(e) => {
+                    eprintln!("warning: {}", e);
+                    listing.push((i.into(), Entry::Data(vec![region.data[i - region.base]])));
+                    i += 1;
+                }
+            }
+        }
+    }
+    listing
+}
+
+fn load(opt: &Opt) -> Result<Image> {
+    let mut image = Image::default();
+    for file in &opt.files {
+        let (path, base) = match file.rsplit_once('@') {
+            Some((path, addr)) => (path, parse_addr(addr)?),
+            None => (file.as_str(), opt.base),
+        };
+
+        if Format::from_path(path) == Format::Binary {
+            let data = fs::read(path)?;
+            let data = data.get(opt.skip..).ok_or_else(|| anyhow!("{}: --skip is past the end of the file", path))?;
+            let data = match opt.length {
+                Some(length) => data.get(..length).ok_or_else(|| anyhow!("{}: --length is past the end of the file", path))?,
+                None => data,
+            };
+            image.insert(base as usize, data);
+            continue;
+        }
+
+        let loaded = Image::load(path, base as usize).map_err(|e| anyhow!("{}: {}", path, e))?;
+        for seg in loaded.segments() {
+            image.insert(seg.addr, &seg.data);
+        }
+        image.entry = loaded.entry.or(image.entry);
+    }
+    Ok(image)
+}
+
+fn bank_map(opt: &Opt) -> Result<Option<BankMap>> {
+    let layout = match &opt.banks {
+        Some(layout) => layout,
+        None => return Ok(None),
+    };
+    let selector = match (opt.bank_port, opt.bank_latch) {
+        (Some(port), None) => Selector::Port(port),
+        (None, Some(addr)) => Selector::Address(addr),
+        _ => return Err(anyhow!("--banks needs one of --bank-port or --bank-latch")),
+    };
+    match layout.as_str() {
+        "pc8300" => Ok(Some(BankMap::pc8300(selector))),
+        _ => Err(anyhow!("unknown bank layout {:?} (expected pc8300)", layout)),
+    }
 }
 
 fn main() -> Result<()> {
     let opt = Opt::from_args();
-	let rom = fs::read(opt.file)?;
-    let mut i = 0;
-    while i < rom.len() {
-        let (cnt, inst) = i8085::Instruction::decode_one(&rom[i..]);
-        if !opt.raw {
-            println!("${:04x}    {:x?}           {}", i, &rom[i..i+cnt], inst.raw_asm());
+    let image = load(&opt)?;
+    let bank_map = bank_map(&opt)?;
+
+    let (first, last) = image.bounds().ok_or_else(|| anyhow!("nothing to disassemble"))?;
+    if last > 0x10000 && bank_map.is_none() {
+        return Err(anyhow!("image runs past 0xffff; is it banked?"));
+    }
+    let mem = MemoryImage::from(&image);
+    // banks are found by their offset in this
+    let (_, file) = image.flatten(0xff);
+    let start = opt.start.map_or(first, usize::from);
+    let end = opt.end.unwrap_or(last);
+
+    let mut user_symbols = Symbols::default();
+    for path in &opt.symbols {
+        user_symbols.merge(Symbols::load(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?);
+    }
+
+    let (sections, cfg, xrefs) = if opt.linear {
+        if bank_map.is_some() {
+            return Err(anyhow!("--linear can't follow bank switches; leave out --banks"));
+        }
+        if opt.graph.is_some() {
+            return Err(anyhow!("--graph needs traced code; leave out --linear"));
+        }
+        // start decoding at --start, so we're in step with its instructions
+        (vec![trace::Section { bank: None, mem: Cow::Borrowed(&mem), listing: linear(&mem, start, opt.cpu) }], None, Xrefs::default())
+    } else {
+        // earlier entries win where they disagree, and the interrupt vectors are the least
+        // sure to be code, so they go last
+        let (reset, vectors) = trace::ENTRY_POINTS.split_at(1);
+        let mut entries = reset.to_vec();
+        entries.extend(&opt.entries);
+        entries.extend(image.entry.map(|entry| entry as u16));
+        // anything the user has named as code is worth tracing from too
+        entries.extend(user_symbols.iter()
+            .filter(|(_, sym)| sym.kind == SymbolKind::Code)
+            .map(|(at, _)| at.addr as u16));
+        entries.extend(vectors);
+        let tracer = match &bank_map {
+            Some(map) => i8085::Tracer::banked(map, &file),
+            None => i8085::Tracer::new(&mem),
+        };
+        let trace = tracer.with_cpu(opt.cpu).trace(&entries);
+        for e in trace.errors() {
+            eprintln!("warning: {}", e);
+        }
+        if trace.instructions().next().is_none() {
+            eprintln!("warning: no code found from the entry points; try --entry");
+        }
+        let cfg = opt.graph.map(|_| Cfg::from_trace(&trace));
+        let xrefs = trace.xrefs().clone();
+        (trace.into_sections(), cfg, xrefs)
+    };
+
+    // banks tell apart the same address in different places, so one set of labels covers
+    // every section
+    let mut symbols = generate_labels(sections.iter().flat_map(|section| &section.listing));
+    symbols.merge(user_symbols);
+
+    if let Some(path) = &opt.export_symbols {
+        symbols.write(&mut fs::File::create(path)?)?;
+    }
+
+    if let (Some(format), Some(cfg)) = (opt.graph, &cfg) {
+        let mut graphs = GraphWriter::new(cfg, format).with_symbols(symbols);
+        if opt.show_calls {
+            graphs = graphs.with_calls();
+        }
+        let mut out = std::io::stdout();
+        if opt.call_graph {
+            graphs.call_graph(&mut out)?;
         } else {
-            println!("{}", inst.raw_asm());
+            for function in cfg.functions().filter(|f| opt.function.map_or(true, |addr| f.entry.addr == addr as usize)) {
+                graphs.function(&mut out, function.entry)?;
+            }
         }
-        i += cnt;
+        return Ok(());
     }
 
+    let data = DataFormat { words: opt.words, ..DataFormat::default() };
+    let mut layout = if opt.raw { Layout::source() } else { Layout::listing() };
+    if !opt.columns.is_empty() {
+        layout.columns = opt.columns.clone();
+    }
+    layout.hex = opt.hex.unwrap_or(match opt.syntax {
+        Syntax::Zilog | Syntax::Tdl => HexStyle::Suffix,
+        Syntax::Raw | Syntax::Intel => HexStyle::Prefix,
+    });
+    layout.uppercase = opt.uppercase;
+    let bank_names = bank_map.as_ref().map(BankMap::names).unwrap_or_default();
+    let mut out = std::io::stdout();
+    if opt.format == OutputFormat::Source && bank_map.is_some() {
+        return Err(anyhow!("can't write source for a banked image"));
+    }
+    // json covers every section in one document, and jsonl has one header for them all
+    let mut segments = Vec::new();
+    if opt.format == OutputFormat::Jsonl {
+        serde_json::to_writer(&mut out, &json::Header::new(&opt.cpu.to_string(), bank_names.clone()))?;
+        writeln!(out)?;
+    }
+    for section in sections {
+        if let (Some(bank), OutputFormat::Listing) = (section.bank, opt.format) {
+            writeln!(out, "; bank {}", bank_names[bank as usize])?;
+        }
+        let listing: Vec<_> = section.listing.into_iter().filter(|(at, _)| (start..end).contains(&at.addr)).collect();
+        let mut printer = Printer::new(listing, AddressWidth::Bits16)
+            .with_bank_names(bank_names.clone())
+            .with_symbols(symbols.clone())
+            .with_data(data.clone())
+            .with_layout(layout.clone())
+            .with_syntax(opt.syntax)
+            .with_memory(section.mem.into_owned());
+        if opt.cycles || layout.columns.contains(&Column::Cycles) {
+            printer = printer.with_cycles(opt.cpu);
+        }
+        if opt.xrefs {
+            printer = printer.with_xrefs(xrefs.clone());
+        }
+        match opt.format {
+            OutputFormat::Listing => printer.print(&mut out)?,
+            OutputFormat::Source => printer.print_source(&mut out, &opt.cpu.to_string())?,
+            OutputFormat::Json => segments.push(printer.segment()),
+            OutputFormat::Jsonl => printer.print_jsonl_lines(&mut out)?,
+        }
+    }
+    if opt.format == OutputFormat::Json {
+        let document = json::Document::new(&opt.cpu.to_string(), segments, &symbols, &xrefs);
+        serde_json::to_writer_pretty(&mut out, &document)?;
+        writeln!(out)?;
+    }
 
-	Ok(())
+    Ok(())
 }
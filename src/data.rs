@@ -0,0 +1,290 @@
+//! Laying out bytes which aren't code as assembler data directives: `db` and `dw` lines,
+//! strings, and runs of padding.
+
+use std::io::{self, Write};
+
+use crate::flow::Flow;
+use crate::printer::{Address, BankedAddress, LabelKind, Operands, Print};
+use crate::symbols::Symbols;
+use crate::syntax::Syntax;
+
+/// How a string ends, besides running into something else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    None,
+    /// A 0 byte after the text
+    Nul,
+    /// Bit 7 set on the last character
+    HighBit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Directive {
+    Bytes(Vec<u8>),
+    /// Little-endian
+    Words(Vec<u16>),
+    /// Printable ASCII; with [`Terminator::HighBit`], the last character is shown without
+    /// bit 7.
+    String { text: String, terminator: Terminator },
+    /// `count` copies of `value`
+    Fill { value: u8, count: usize },
+}
+
+impl Directive {
+    /// Bytes this covers.
+    pub fn len(&self) -> usize {
+        match self {
+            Directive::Bytes(bytes) => bytes.len(),
+            Directive::Words(words) => words.len() * 2,
+            Directive::String { text, terminator: Terminator::Nul } => text.len() + 1,
+            Directive::String { text, .. } => text.len(),
+            Directive::Fill { count, .. } => *count,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Print for Directive {
+    // data has none, and takes no time
+    type Operand = ();
+    type Cpu = ();
+
+    fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        match self {
+            Directive::Bytes(bytes) => {
+                let bytes: Vec<String> = bytes.iter().map(|b| format!("{:#04x}", b)).collect();
+                write!(w, "db {}", bytes.join(", "))
+            }
+            Directive::Words(words) => {
+                let words: Vec<String> = words.iter().map(|word| format!("{:#06x}", word)).collect();
+                write!(w, "dw {}", words.join(", "))
+            }
+            Directive::String { text, terminator } => match terminator {
+                Terminator::None => write!(w, "db \"{}\"", text),
+                Terminator::Nul => write!(w, "db \"{}\", 0", text),
+                Terminator::HighBit => {
+                    let (text, last) = text.split_at(text.len() - 1);
+                    if text.is_empty() {
+                        write!(w, "db '{}'|0x80", last)
+                    } else {
+                        write!(w, "db \"{}\", '{}'|0x80", text, last)
+                    }
+                }
+            },
+            Directive::Fill { value, count } => write!(w, "db {} dup ({:#04x})", count, value),
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Directive::Bytes(bytes) => bytes.clone(),
+            Directive::Words(words) => words.iter().flat_map(|word| word.to_le_bytes()).collect(),
+            Directive::String { text, terminator } => {
+                let mut bytes = text.as_bytes().to_vec();
+                match terminator {
+                    Terminator::None => {}
+                    Terminator::Nul => bytes.push(0),
+                    Terminator::HighBit => *bytes.last_mut().expect("strings aren't empty") |= 0x80,
+                }
+                bytes
+            }
+            Directive::Fill { value, count } => vec![*value; *count],
+        }
+    }
+}
+
+/// Whether `b` can go in a string literal as it is.
+fn printable(b: u8) -> bool {
+    (0x20..0x7f).contains(&b) && b != b'"' && b != b'\\'
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFormat {
+    /// Most bytes on a `db` or `dw` line
+    pub per_line: usize,
+    /// Show bytes as little-endian words
+    pub words: bool,
+    /// Shortest text, counting its terminator, shown as a string
+    pub min_string: usize,
+    /// Shortest run of one value shown as a fill
+    pub min_fill: usize,
+}
+
+impl Default for DataFormat {
+    fn default() -> DataFormat {
+        DataFormat { per_line: 8, words: false, min_string: 4, min_fill: 8 }
+    }
+}
+
+impl DataFormat {
+    fn fill_at(&self, bytes: &[u8]) -> Option<Directive> {
+        let value = *bytes.first()?;
+        let count = bytes.iter().take_while(|&&b| b == value).count();
+        Some(Directive::Fill { value, count }).filter(|_| count >= self.min_fill)
+    }
+
+    fn string_at(&self, bytes: &[u8]) -> Option<Directive> {
+        let len = bytes.iter().take_while(|&&b| printable(b)).count();
+        let mut text: String = bytes[..len].iter().map(|&b| b as char).collect();
+        let terminator = match bytes.get(len) {
+            Some(0) => Terminator::Nul,
+            Some(&b) if b & 0x80 != 0 && printable(b & 0x7f) && b & 0x7f != b'\'' => {
+                text.push((b & 0x7f) as char);
+                Terminator::HighBit
+            }
+            _ => Terminator::None,
+        };
+        let string = Directive::String { text, terminator };
+        Some(string).filter(|s| s.len() >= self.min_string)
+    }
+
+    fn raw(&self, bytes: &[u8], lines: &mut Vec<Directive>) {
+        if !self.words {
+            lines.push(Directive::Bytes(bytes.to_vec()));
+            return;
+        }
+        let words = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        lines.push(Directive::Words(words));
+        if bytes.len() % 2 == 1 {
+            lines.push(Directive::Bytes(vec![bytes[bytes.len() - 1]]));
+        }
+    }
+
+    /// Directives for `bytes`, each with its offset into them.
+    pub fn directives(&self, bytes: &[u8]) -> Vec<(usize, Directive)> {
+        let per_line = self.per_line.max(1);
+        let mut lines = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if let Some(run) = self.fill_at(&bytes[i..]).or_else(|| self.string_at(&bytes[i..])) {
+                i += run.len();
+                lines.push(run);
+                continue;
+            }
+            let start = i;
+            i += 1;
+            while i < bytes.len() && i - start < per_line
+                && self.fill_at(&bytes[i..]).is_none() && self.string_at(&bytes[i..]).is_none()
+            {
+                i += 1;
+            }
+            self.raw(&bytes[start..i], &mut lines);
+        }
+
+        let mut offset = 0;
+        lines.into_iter().map(|line| {
+            let at = offset;
+            offset += line.len();
+            (at, line)
+        }).collect()
+    }
+
+    /// The lines of `listing`, with its runs of data laid out as directives. Runs are split
+    /// wherever `split` says, so labels and the like still have a line to go on.
+    pub fn lines<'a, I, F>(&self, listing: &'a [(BankedAddress, I)], split: F) -> Vec<(BankedAddress, Line<'a, I>)>
+        where I: Print, F: Fn(BankedAddress) -> bool
+    {
+        let mut lines = Vec::new();
+        let mut run: Option<(BankedAddress, Vec<u8>)> = None;
+        let flush = |run: Option<(BankedAddress, Vec<u8>)>, lines: &mut Vec<(BankedAddress, Line<'a, I>)>| {
+            let (start, bytes) = match run {
+                Some(run) => run,
+                None => return,
+            };
+            let mut from = 0;
+            for i in 1..=bytes.len() {
+                if i == bytes.len() || split(start.with_addr(start.addr + i)) {
+                    for (offset, directive) in self.directives(&bytes[from..i]) {
+                        lines.push((start.with_addr(start.addr + from + offset), Line::Data(directive)));
+                    }
+                    from = i;
+                }
+            }
+        };
+
+        for (at, item) in listing {
+            let data = match item.data() {
+                Some(data) => data,
+                None => {
+                    flush(run.take(), &mut lines);
+                    lines.push((*at, Line::Code(item)));
+                    continue;
+                }
+            };
+            match &mut run {
+                Some((start, bytes)) if start.bank == at.bank && start.addr + bytes.len() == at.addr => {
+                    bytes.extend_from_slice(data);
+                }
+                _ => {
+                    flush(run.take(), &mut lines);
+                    run = Some((*at, data.to_vec()));
+                }
+            }
+        }
+        flush(run, &mut lines);
+        lines
+    }
+}
+
+/// A line of a listing after [`DataFormat::lines`]: what was there, or a data directive.
+pub enum Line<'a, I> {
+    Code(&'a I),
+    Data(Directive),
+}
+
+impl<'a, I> Print for Line<'a, I> where I: Print {
+    type Operand = I::Operand;
+    type Cpu = I::Cpu;
+
+    fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        match self {
+            Line::Code(item) => item.print(w),
+            Line::Data(directive) => directive.print(w),
+        }
+    }
+
+    fn print_labelled<W>(&self, w: &mut W, symbols: &Symbols) -> io::Result<()> where W: Write {
+        match self {
+            Line::Code(item) => item.print_labelled(w, symbols),
+            Line::Data(directive) => directive.print(w),
+        }
+    }
+
+    fn print_syntax<W>(&self, w: &mut W, symbols: &Symbols, syntax: Syntax) -> io::Result<()> where W: Write {
+        match self {
+            Line::Code(item) => item.print_syntax(w, symbols, syntax),
+            Line::Data(directive) => directive.print(w),
+        }
+    }
+
+    fn references(&self) -> Vec<(Address, LabelKind)> {
+        match self {
+            Line::Code(item) => item.references(),
+            Line::Data(_) => Vec::new(),
+        }
+    }
+
+    fn code(&self) -> Option<(Operands<I::Operand>, Flow)> {
+        match self {
+            Line::Code(item) => item.code(),
+            Line::Data(_) => None,
+        }
+    }
+
+    fn timing(&self, cpu: I::Cpu) -> Option<String> {
+        match self {
+            Line::Code(item) => item.timing(cpu),
+            Line::Data(_) => None,
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Line::Code(item) => item.bytes(),
+            Line::Data(directive) => directive.bytes(),
+        }
+    }
+}
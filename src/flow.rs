@@ -0,0 +1,42 @@
+//! How control leaves an instruction, whatever the processor.
+
+/// How an instruction hands control to whatever executes after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Execution continues with the next instruction.
+    Fallthrough,
+    /// Jumps to `target` if a condition holds, otherwise falls through.
+    ConditionalBranch { target: u16 },
+    /// Always jumps to `target`.
+    Branch { target: u16 },
+    /// Calls the subroutine at `target` (possibly conditionally), then resumes after it.
+    Call { target: u16 },
+    Return,
+    /// Returns if a condition holds, otherwise falls through.
+    ConditionalReturn,
+    /// Jumps to an address only known at runtime (PCHL).
+    Indirect,
+    /// Stops until an interrupt arrives; execution resumes after the HLT once it's serviced.
+    Halt,
+    /// Software interrupt through `vector` (RST n, RSTV), returning to the next instruction.
+    Trap { vector: u16 },
+}
+
+impl Flow {
+    /// The statically known address control may transfer to, if any.
+    pub fn target(&self) -> Option<u16> {
+        match *self {
+            Flow::ConditionalBranch { target }
+            | Flow::Branch { target }
+            | Flow::Call { target } => Some(target),
+            Flow::Trap { vector } => Some(vector),
+            _ => None,
+        }
+    }
+
+    /// Whether the next instruction can execute after this one (including on return from a
+    /// call or trap).
+    pub fn falls_through(&self) -> bool {
+        !matches!(self, Flow::Branch { .. } | Flow::Return | Flow::Indirect)
+    }
+}
@@ -0,0 +1,212 @@
+//! Basic blocks and the control flow between them, for traced code.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::trace::Trace;
+use super::{Flow, Instruction};
+use crate::printer::{Address, BankedAddress, Starts};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    /// Straight on into the next block, including after a call or trap returns.
+    Fallthrough,
+    /// A jump, conditional or not, being taken.
+    Taken,
+    /// A conditional jump or return not being taken.
+    NotTaken,
+    /// Into a subroutine, by CALL or RST.
+    Call,
+    /// From a returning block back to just after a call to its function.
+    Return,
+}
+
+/// An edge between the blocks starting at `from` and `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+    pub from: BankedAddress,
+    pub to: BankedAddress,
+    pub kind: EdgeKind,
+}
+
+/// A run of instructions only ever entered at the top and left at the bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: BankedAddress,
+    /// One past its last byte.
+    pub end: Address,
+    pub instructions: Vec<(BankedAddress, Instruction)>,
+}
+
+impl Block {
+    pub fn last(&self) -> &Instruction {
+        &self.instructions.last().expect("blocks aren't empty").1
+    }
+}
+
+/// The blocks reachable from an entry point or call target without going through another
+/// call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub entry: BankedAddress,
+    pub blocks: BTreeSet<BankedAddress>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Cfg {
+    blocks: BTreeMap<BankedAddress, Block>,
+    edges: BTreeSet<Edge>,
+    functions: BTreeMap<BankedAddress, Function>,
+}
+
+impl Cfg {
+    pub fn from_trace(trace: &Trace) -> Cfg {
+        Cfg::build(trace.instructions(), &trace.entries())
+    }
+
+    /// Splits `instructions`, given as `(address, length, instruction)`, into blocks, with
+    /// functions at `entries` and every call target. Targets are looked for in the same bank
+    /// as the instruction first, then in whichever bank has an instruction there, if only one
+    /// does.
+    pub fn build<'a, I>(instructions: I, entries: &[BankedAddress]) -> Cfg
+        where I: IntoIterator<Item = (BankedAddress, usize, &'a Instruction)>
+    {
+        let instrs: BTreeMap<BankedAddress, (usize, &Instruction)> = instructions.into_iter()
+            .map(|(at, len, instr)| (at, (len, instr)))
+            .collect();
+        let starts = Starts::new(instrs.keys().copied());
+        let resolve = |from: BankedAddress, target: u16| starts.resolve_target(from, target as Address);
+
+        // a block starts wherever something other than the instruction before it leads
+        let mut leaders = BTreeSet::new();
+        let mut continues = None;
+        for (&at, &(len, instr)) in &instrs {
+            let flow = instr.flow();
+            if continues != Some(at) {
+                leaders.insert(at);
+            }
+            leaders.extend(flow.target().and_then(|target| resolve(at, target)));
+            continues = match flow {
+                Flow::Fallthrough => Some(at.with_addr(at.addr + len)),
+                _ => None,
+            };
+        }
+
+        let mut cfg = Cfg::default();
+        let mut block: Option<Block> = None;
+        for (&at, &(len, instr)) in &instrs {
+            if leaders.contains(&at) {
+                cfg.blocks.extend(block.take().map(|b| (b.start, b)));
+            }
+            let b = block.get_or_insert_with(|| Block { start: at, end: at.addr, instructions: Vec::new() });
+            b.instructions.push((at, instr.clone()));
+            b.end = at.addr + len;
+        }
+        cfg.blocks.extend(block.map(|b| (b.start, b)));
+
+        let mut edges = BTreeSet::new();
+        for block in cfg.blocks.values() {
+            let next = block.start.with_addr(block.end);
+            let next = Some(next).filter(|next| instrs.contains_key(next));
+            let mut edge = |to: Option<BankedAddress>, kind| {
+                if let Some(to) = to {
+                    edges.insert(Edge { from: block.start, to, kind });
+                }
+            };
+            let (at, _) = block.instructions.last().expect("blocks aren't empty");
+            match block.last().flow() {
+                Flow::Fallthrough | Flow::Halt => edge(next, EdgeKind::Fallthrough),
+                Flow::ConditionalBranch { target } => {
+                    edge(resolve(*at, target), EdgeKind::Taken);
+                    edge(next, EdgeKind::NotTaken);
+                }
+                Flow::Branch { target } => edge(resolve(*at, target), EdgeKind::Taken),
+                Flow::Call { target } | Flow::Trap { vector: target } => {
+                    edge(resolve(*at, target), EdgeKind::Call);
+                    edge(next, EdgeKind::Fallthrough);
+                }
+                Flow::ConditionalReturn => edge(next, EdgeKind::NotTaken),
+                Flow::Return | Flow::Indirect => {}
+            }
+        }
+
+        cfg.edges = edges;
+        cfg.find_functions(entries);
+        cfg
+    }
+
+    fn find_functions(&mut self, entries: &[BankedAddress]) {
+        let calls: Vec<Edge> = self.edges.iter().filter(|e| e.kind == EdgeKind::Call).copied().collect();
+        // reset and the interrupt handlers are only entered through their vectors
+        let roots: BTreeSet<BankedAddress> = calls.iter().map(|e| e.to)
+            .chain(entries.iter().copied().filter(|at| self.blocks.contains_key(at)))
+            .collect();
+
+        for &entry in &roots {
+            let mut blocks = BTreeSet::new();
+            let mut pending = VecDeque::from(vec![entry]);
+            while let Some(at) = pending.pop_front() {
+                // jumping to another function's entry is a tail call, not part of this one
+                if (at != entry && roots.contains(&at)) || !blocks.insert(at) {
+                    continue;
+                }
+                pending.extend(self.successors(at)
+                    .filter(|e| matches!(e.kind, EdgeKind::Fallthrough | EdgeKind::Taken | EdgeKind::NotTaken))
+                    .map(|e| e.to));
+            }
+            self.functions.insert(entry, Function { entry, blocks });
+        }
+
+        // returns go back to just after each call
+        let mut returns = Vec::new();
+        for call in calls {
+            let site = self.successors(call.from).find(|e| e.kind == EdgeKind::Fallthrough).map(|e| e.to);
+            let (site, function) = match (site, self.functions.get(&call.to)) {
+                (Some(site), Some(function)) => (site, function),
+                _ => continue,
+            };
+            for from in &function.blocks {
+                if matches!(self.blocks[from].last().flow(), Flow::Return | Flow::ConditionalReturn) {
+                    returns.push(Edge { from: *from, to: site, kind: EdgeKind::Return });
+                }
+            }
+        }
+        self.edges.extend(returns);
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: BankedAddress) -> Option<&Block> {
+        self.blocks.get(&start)
+    }
+
+    /// The block holding the instruction at `at`.
+    pub fn block_containing(&self, at: BankedAddress) -> Option<&Block> {
+        self.blocks.range(..=at).next_back()
+            .map(|(_, block)| block)
+            .filter(|block| block.start.bank == at.bank && at.addr < block.end)
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter()
+    }
+
+    pub fn successors(&self, block: BankedAddress) -> impl Iterator<Item = &Edge> {
+        // edges sort by where they're from
+        let first = Edge { from: block, to: BankedAddress::default(), kind: EdgeKind::Fallthrough };
+        self.edges.range(first..).take_while(move |e| e.from == block)
+    }
+
+    pub fn predecessors(&self, block: BankedAddress) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |e| e.to == block)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.values()
+    }
+
+    pub fn function(&self, entry: BankedAddress) -> Option<&Function> {
+        self.functions.get(&entry)
+    }
+}
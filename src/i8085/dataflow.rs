@@ -0,0 +1,399 @@
+//! Which registers, flags and memory each instruction reads and writes.
+//!
+//! The undocumented V and K flags follow the usual descriptions of the 8085's undocumented
+//! behaviour: V is set by arithmetic with signed overflow, K by INX/DCX wrapping around and
+//! by the same arithmetic that sets V.
+
+use bitflags::bitflags;
+
+use super::{ConditionCodes, Instruction, Register, RegisterPair};
+
+bitflags! {
+    /// A set of registers. Pairs count as both of their halves; SP is a single 16-bit
+    /// register.
+    #[derive(Default)]
+    pub struct Regs: u8 {
+        const A = 1 << 0;
+        const B = 1 << 1;
+        const C = 1 << 2;
+        const D = 1 << 3;
+        const E = 1 << 4;
+        const H = 1 << 5;
+        const L = 1 << 6;
+        const SP = 1 << 7;
+    }
+}
+
+bitflags! {
+    /// PSW flags, laid out as in the flag register.
+    #[derive(Default)]
+    pub struct Flags: u8 {
+        /// Sign
+        const S = 1 << 7;
+        /// Zero
+        const Z = 1 << 6;
+        /// Undocumented: 16-bit under/overflow (sometimes called X5 or UI)
+        const K = 1 << 5;
+        /// Auxiliary carry
+        const AC = 1 << 4;
+        /// Parity
+        const P = 1 << 2;
+        /// Undocumented: signed overflow
+        const V = 1 << 1;
+        /// Carry
+        const CY = 1 << 0;
+    }
+}
+
+/// How an instruction addresses memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemRef {
+    /// (HL), including `m` operands
+    Hl,
+    /// (BC)
+    Bc,
+    /// (DE)
+    De,
+    /// The top of the stack, at SP
+    Stack,
+    /// A fixed address encoded in the instruction
+    Absolute(u16),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Effects {
+    pub uses: Regs,
+    pub defs: Regs,
+    pub flags_used: Flags,
+    pub flags_defined: Flags,
+    pub mem_read: Option<MemRef>,
+    pub mem_written: Option<MemRef>,
+}
+
+/// Flags set by 8-bit add/subtract/compare.
+const ARITH: Flags = Flags::from_bits_truncate(
+    Flags::S.bits | Flags::Z.bits | Flags::AC.bits | Flags::P.bits | Flags::CY.bits
+    | Flags::V.bits | Flags::K.bits);
+/// Flags set by INR/DCR, which leave CY alone.
+const INCDEC: Flags = Flags::from_bits_truncate(ARITH.bits & !Flags::CY.bits);
+/// Flags set by ANA/ORA/XRA and their immediate forms.
+const LOGIC: Flags = Flags::from_bits_truncate(
+    Flags::S.bits | Flags::Z.bits | Flags::AC.bits | Flags::P.bits | Flags::CY.bits);
+
+impl From<Register> for Regs {
+    /// `m` isn't a register, so maps to the empty set.
+    fn from(reg: Register) -> Regs {
+        match reg {
+            Register::A => Regs::A,
+            Register::B => Regs::B,
+            Register::C => Regs::C,
+            Register::D => Regs::D,
+            Register::E => Regs::E,
+            Register::H => Regs::H,
+            Register::L => Regs::L,
+            Register::Mem => Regs::empty(),
+        }
+    }
+}
+
+impl From<RegisterPair> for Regs {
+    /// PSW maps to just A; its flag half is tracked as [`Flags`].
+    fn from(reg_pair: RegisterPair) -> Regs {
+        match reg_pair {
+            RegisterPair::BC => Regs::B | Regs::C,
+            RegisterPair::DE => Regs::D | Regs::E,
+            RegisterPair::HL => Regs::H | Regs::L,
+            RegisterPair::SP => Regs::SP,
+            RegisterPair::PSW => Regs::A,
+        }
+    }
+}
+
+impl ConditionCodes {
+    /// The flag this condition tests.
+    pub fn flag(&self) -> Flags {
+        use ConditionCodes::*;
+        match self {
+            NZ | Z => Flags::Z,
+            NC | C => Flags::CY,
+            PO | PE => Flags::P,
+            P | M => Flags::S,
+        }
+    }
+}
+
+fn ptr(reg_pair: RegisterPair) -> MemRef {
+    match reg_pair {
+        RegisterPair::BC => MemRef::Bc,
+        RegisterPair::DE => MemRef::De,
+        _ => MemRef::Hl,
+    }
+}
+
+impl Effects {
+    fn read(&mut self, reg: Register) {
+        if reg == Register::Mem {
+            self.uses |= Regs::H | Regs::L;
+            self.mem_read = Some(MemRef::Hl);
+        }
+        self.uses |= reg.into();
+    }
+
+    fn write(&mut self, reg: Register) {
+        if reg == Register::Mem {
+            self.uses |= Regs::H | Regs::L;
+            self.mem_written = Some(MemRef::Hl);
+        }
+        self.defs |= reg.into();
+    }
+
+    fn condition(&mut self, condition: Option<ConditionCodes>) {
+        if let Some(cond) = condition {
+            self.flags_used |= cond.flag();
+        }
+    }
+
+    fn push(&mut self) {
+        self.uses |= Regs::SP;
+        self.defs |= Regs::SP;
+        self.mem_written = Some(MemRef::Stack);
+    }
+
+    fn pop(&mut self) {
+        self.uses |= Regs::SP;
+        self.defs |= Regs::SP;
+        self.mem_read = Some(MemRef::Stack);
+    }
+}
+
+impl Instruction {
+    pub fn effects(&self) -> Effects {
+        use Instruction::*;
+        let mut fx = Effects::default();
+        match *self {
+            Nop | Hlt | Ei | Di | Jmp { condition: None, .. } => {}
+
+            Mov { src, dest } => {
+                fx.read(src);
+                fx.write(dest);
+            }
+            Mvi { reg, .. } => fx.write(reg),
+            Lxi { reg, .. } => fx.defs |= reg.into(),
+
+            Stax { ptr: rp } => {
+                fx.uses |= Regs::A | rp.into();
+                fx.mem_written = Some(ptr(rp));
+            }
+            Ldax { ptr: rp } => {
+                fx.uses |= rp.into();
+                fx.defs |= Regs::A;
+                fx.mem_read = Some(ptr(rp));
+            }
+
+            Inx { reg_pair } | Dcx { reg_pair } => {
+                fx.uses |= reg_pair.into();
+                fx.defs |= reg_pair.into();
+                fx.flags_defined |= Flags::K;
+            }
+            Inr { reg } | Dcr { reg } => {
+                fx.read(reg);
+                fx.write(reg);
+                fx.flags_defined |= INCDEC;
+            }
+            Dad { reg_pair } => {
+                fx.uses |= Regs::H | Regs::L | reg_pair.into();
+                fx.defs |= Regs::H | Regs::L;
+                fx.flags_defined |= Flags::CY;
+            }
+
+            Rlc | Rrc => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_defined |= Flags::CY;
+            }
+            Ral | Rar => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_used |= Flags::CY;
+                fx.flags_defined |= Flags::CY;
+            }
+
+            Add { reg } | Sub { reg } => {
+                fx.read(reg);
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_defined |= ARITH;
+            }
+            Adc { reg } | Sbb { reg } => {
+                fx.read(reg);
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_used |= Flags::CY;
+                fx.flags_defined |= ARITH;
+            }
+            Ana { reg } | Ora { reg } | Xra { reg } => {
+                fx.read(reg);
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_defined |= LOGIC;
+            }
+            Cmp { reg } => {
+                fx.read(reg);
+                fx.uses |= Regs::A;
+                fx.flags_defined |= ARITH;
+            }
+
+            Adi { .. } | Sui { .. } => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_defined |= ARITH;
+            }
+            Aci { .. } | Sbi { .. } => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_used |= Flags::CY;
+                fx.flags_defined |= ARITH;
+            }
+            Ani { .. } | Ori { .. } | Xri { .. } => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_defined |= LOGIC;
+            }
+            Cpi { .. } => {
+                fx.uses |= Regs::A;
+                fx.flags_defined |= ARITH;
+            }
+
+            Rim => fx.defs |= Regs::A,
+            Sim => fx.uses |= Regs::A,
+
+            In { .. } => fx.defs |= Regs::A,
+            Out { .. } => fx.uses |= Regs::A,
+
+            Lda { addr } => {
+                fx.defs |= Regs::A;
+                fx.mem_read = Some(MemRef::Absolute(addr));
+            }
+            Sta { addr } => {
+                fx.uses |= Regs::A;
+                fx.mem_written = Some(MemRef::Absolute(addr));
+            }
+            Lhld { addr } => {
+                fx.defs |= Regs::H | Regs::L;
+                fx.mem_read = Some(MemRef::Absolute(addr));
+            }
+            Shld { addr } => {
+                fx.uses |= Regs::H | Regs::L;
+                fx.mem_written = Some(MemRef::Absolute(addr));
+            }
+
+            Daa => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+                fx.flags_used |= Flags::AC | Flags::CY;
+                fx.flags_defined |= LOGIC;
+            }
+            Stc => fx.flags_defined |= Flags::CY,
+            Cmc => {
+                fx.flags_used |= Flags::CY;
+                fx.flags_defined |= Flags::CY;
+            }
+            Cma => {
+                fx.uses |= Regs::A;
+                fx.defs |= Regs::A;
+            }
+
+            Jmp { condition, .. } => fx.condition(condition),
+            Call { condition, .. } => {
+                fx.condition(condition);
+                fx.push();
+            }
+            Ret { condition } => {
+                fx.condition(condition);
+                fx.pop();
+            }
+            Rst { .. } => fx.push(),
+            Rstv => {
+                fx.flags_used |= Flags::V;
+                fx.push();
+            }
+            Jk { .. } | Jnk { .. } => fx.flags_used |= Flags::K,
+
+            Push { reg_pair } => {
+                fx.uses |= reg_pair.into();
+                if reg_pair == RegisterPair::PSW {
+                    fx.flags_used = Flags::all();
+                }
+                fx.push();
+            }
+            Pop { reg_pair } => {
+                fx.defs |= reg_pair.into();
+                if reg_pair == RegisterPair::PSW {
+                    fx.flags_defined = Flags::all();
+                }
+                fx.pop();
+            }
+
+            Xthl => {
+                fx.uses |= Regs::H | Regs::L | Regs::SP;
+                fx.defs |= Regs::H | Regs::L;
+                fx.mem_read = Some(MemRef::Stack);
+                fx.mem_written = Some(MemRef::Stack);
+            }
+            Xchg => {
+                fx.uses |= Regs::D | Regs::E | Regs::H | Regs::L;
+                fx.defs |= Regs::D | Regs::E | Regs::H | Regs::L;
+            }
+            Pchl => fx.uses |= Regs::H | Regs::L,
+            Sphl => {
+                fx.uses |= Regs::H | Regs::L;
+                fx.defs |= Regs::SP;
+            }
+
+            Dsub => {
+                fx.uses |= Regs::H | Regs::L | Regs::B | Regs::C;
+                fx.defs |= Regs::H | Regs::L;
+                fx.flags_defined |= ARITH;
+            }
+            Arhl => {
+                fx.uses |= Regs::H | Regs::L;
+                fx.defs |= Regs::H | Regs::L;
+                fx.flags_defined |= Flags::CY;
+            }
+            Rdel => {
+                fx.uses |= Regs::D | Regs::E;
+                fx.defs |= Regs::D | Regs::E;
+                fx.flags_used |= Flags::CY;
+                fx.flags_defined |= Flags::CY | Flags::V;
+            }
+            Ldhi { .. } => {
+                fx.uses |= Regs::H | Regs::L;
+                fx.defs |= Regs::D | Regs::E;
+            }
+            Ldsi { .. } => {
+                fx.uses |= Regs::SP;
+                fx.defs |= Regs::D | Regs::E;
+            }
+            Shlx => {
+                fx.uses |= Regs::D | Regs::E | Regs::H | Regs::L;
+                fx.mem_written = Some(MemRef::De);
+            }
+            Lhlx => {
+                fx.uses |= Regs::D | Regs::E;
+                fx.defs |= Regs::H | Regs::L;
+                fx.mem_read = Some(MemRef::De);
+            }
+        }
+        fx
+    }
+
+    /// Registers this instruction reads.
+    pub fn uses(&self) -> Regs {
+        self.effects().uses
+    }
+
+    /// Registers this instruction writes.
+    pub fn defs(&self) -> Regs {
+        self.effects().defs
+    }
+}
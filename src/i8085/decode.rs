@@ -1,11 +1,133 @@
-use super::{Register, Instruction, RegisterPair, ConditionCodes};
+// opcode literals are grouped by instruction field (eg. 01DDDSSS), not nibble
+#![allow(clippy::unusual_byte_groupings)]
+
+use std::error::Error;
+use std::fmt;
+
+use crate::memory::MemoryImage;
+use super::{Cpu, Register, Instruction, RegisterPair, ConditionCodes};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// No instruction is encoded by `opcode`.
+    UnknownOpcode { addr: usize, opcode: u8 },
+    /// The buffer ends before the instruction's operands do.
+    Truncated { addr: usize, needed: usize, available: usize },
+    /// Nothing readable at `addr`: it's unmapped, MMIO, or of unknown contents.
+    Unreadable { addr: usize },
+}
+
+impl DecodeError {
+    pub fn addr(&self) -> usize {
+        match *self {
+            DecodeError::UnknownOpcode { addr, .. } => addr,
+            DecodeError::Truncated { addr, .. } => addr,
+            DecodeError::Unreadable { addr } => addr,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode { addr, opcode } =>
+                write!(f, "unknown opcode {:#04x} at {:#06x}", opcode, addr),
+            DecodeError::Truncated { addr, needed, available } =>
+                write!(f, "truncated instruction at {:#06x}: needs {} bytes, {} available", addr, needed, available),
+            DecodeError::Unreadable { addr } =>
+                write!(f, "nothing readable at {:#06x}", addr),
+        }
+    }
+}
+
+impl Error for DecodeError {}
 
 fn lohi(lo: u8, hi: u8) -> u16 {
     ((hi as u16) << 8) | lo as u16
 }
 
+/// Opcodes the 8085 has but doesn't document. RIM and SIM aren't here: they're documented,
+/// just not present on the 8080.
+fn is_undocumented(opcode: u8) -> bool {
+    matches!(opcode, 0x08 | 0x10 | 0x18 | 0x28 | 0x38 | 0xcb | 0xd9 | 0xdd | 0xed | 0xfd)
+}
+
+/// What the 8080 executes for opcodes which are new or undocumented on the 8085.
+fn i8080_alias(opcode: u8) -> Option<u8> {
+    match opcode {
+        0x08 | 0x10 | 0x18 | 0x20 | 0x28 | 0x30 | 0x38 => Some(0x00), // NOP
+        0xcb => Some(0xc3), // JMP
+        0xd9 => Some(0xc9), // RET
+        0xdd | 0xed | 0xfd => Some(0xcd), // CALL
+        _ => None,
+    }
+}
+
 impl Instruction {
-      pub fn decode_one(buf: &[u8]) -> (usize, Instruction) {
+    /// Decodes the instruction at the start of `buf`, which sits at `addr` in the image, as
+    /// the 8085 (undocumented instructions included) would.
+    /// Returns the number of bytes consumed along with the instruction.
+    pub fn decode_one(buf: &[u8], addr: usize) -> Result<(usize, Instruction), DecodeError> {
+        Self::decode(buf, addr, Cpu::I8085Undoc)
+    }
+
+    /// Like [`Instruction::decode_one`], but decoding as `cpu` would.
+    ///
+    /// On the 8080, aliases decode as the instruction they alias, so won't re-encode to the
+    /// same bytes.
+    pub fn decode(buf: &[u8], addr: usize, cpu: Cpu) -> Result<(usize, Instruction), DecodeError> {
+        match (cpu, buf.first()) {
+            (Cpu::I8080, Some(&opcode)) => {
+                if let Some(alias) = i8080_alias(opcode) {
+                    let mut aliased = [0u8; 3];
+                    let available = buf.len().min(aliased.len());
+                    aliased[..available].copy_from_slice(&buf[..available]);
+                    aliased[0] = alias;
+                    return Self::decode_bytes(&aliased[..available], addr);
+                }
+            }
+            (Cpu::I8085, Some(&opcode)) if is_undocumented(opcode) => {
+                return Err(DecodeError::UnknownOpcode { addr, opcode });
+            }
+            _ => {}
+        }
+
+        Self::decode_bytes(buf, addr)
+    }
+
+    /// Decodes the instruction at `addr` in `mem` as `cpu` would. Instructions never run
+    /// on past readable memory: one that would is [`DecodeError::Truncated`].
+    pub fn decode_at(mem: &MemoryImage, addr: usize, cpu: Cpu) -> Result<(usize, Instruction), DecodeError> {
+        let buf = mem.fetch(addr, 3);
+        if buf.is_empty() {
+            return Err(DecodeError::Unreadable { addr });
+        }
+        Self::decode(&buf, addr, cpu)
+    }
+
+    fn decode_bytes(buf: &[u8], addr: usize) -> Result<(usize, Instruction), DecodeError> {
+        if let Some(decoded) = Self::decode_raw(buf) {
+            return Ok(decoded);
+        }
+
+        let opcode = match buf.first() {
+            Some(&opcode) => opcode,
+            None => return Err(DecodeError::Truncated { addr, needed: 1, available: 0 }),
+        };
+
+        // the match below won't bind operands that aren't there, so see whether the opcode
+        // decodes once we pad it out to the longest possible instruction.
+        let mut padded = [0u8; 3];
+        let available = buf.len().min(padded.len());
+        padded[..available].copy_from_slice(&buf[..available]);
+        match Self::decode_raw(&padded) {
+            Some((needed, _)) if needed > buf.len() =>
+                Err(DecodeError::Truncated { addr, needed, available: buf.len() }),
+            _ => Err(DecodeError::UnknownOpcode { addr, opcode }),
+        }
+    }
+
+    fn decode_raw(buf: &[u8]) -> Option<(usize, Instruction)> {
         use Instruction::*;
         use ConditionCodes::*;
         let (count, instr) = match *buf {
@@ -367,12 +489,9 @@ impl Instruction {
 
             [0xcb, ..] => (1, Rstv),
 
-            _ => {
-                eprintln!("FAILED TO PARSE: {:x?}", buf.iter().take(3).collect::<Vec<_>>());
-                todo!();
-            }
+            _ => return None,
         };
 
-        (count, instr)
+        Some((count, instr))
     }
 }
@@ -0,0 +1,152 @@
+// opcode literals are grouped by instruction field (eg. 01DDDSSS), not nibble
+#![allow(clippy::unusual_byte_groupings)]
+
+use std::error::Error;
+use std::fmt;
+
+use super::{Instruction, Register, RegisterPair};
+
+/// An instruction with operands that have no encoding, eg. `stax hl` or `push sp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeError(pub String);
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't encode `{}`", self.0)
+    }
+}
+
+impl Error for EncodeError {}
+
+fn reg(r: Register) -> u8 {
+    r.into()
+}
+
+/// The RP field of LXI, INX, DCX and DAD.
+fn rp(instr: &Instruction, pair: RegisterPair) -> Result<u8, EncodeError> {
+    match pair {
+        RegisterPair::BC => Ok(0b00),
+        RegisterPair::DE => Ok(0b01),
+        RegisterPair::HL => Ok(0b10),
+        RegisterPair::SP => Ok(0b11),
+        RegisterPair::PSW => Err(EncodeError(instr.raw_asm())),
+    }
+}
+
+/// The RP field of PUSH and POP, where PSW takes SP's place.
+fn stack_rp(instr: &Instruction, pair: RegisterPair) -> Result<u8, EncodeError> {
+    match pair {
+        RegisterPair::PSW => Ok(0b11),
+        RegisterPair::SP => Err(EncodeError(instr.raw_asm())),
+        _ => rp(instr, pair),
+    }
+}
+
+/// The RP field of LDAX and STAX, which only take BC and DE.
+fn ptr_rp(instr: &Instruction, pair: RegisterPair) -> Result<u8, EncodeError> {
+    match pair {
+        RegisterPair::BC | RegisterPair::DE => rp(instr, pair),
+        _ => Err(EncodeError(instr.raw_asm())),
+    }
+}
+
+fn with_addr(opcode: u8, addr: u16) -> Vec<u8> {
+    let [lo, hi] = addr.to_le_bytes();
+    vec![opcode, lo, hi]
+}
+
+impl Instruction {
+    /// Encodes this instruction; the inverse of [`Instruction::decode_one`].
+    ///
+    /// `mov m, m` encodes as 0x76, so decodes back as `hlt`.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        use Instruction::*;
+        let bytes = match *self {
+            Nop => vec![0x00],
+            Hlt => vec![0x76],
+            Rim => vec![0x20],
+            Sim => vec![0x30],
+            Ei => vec![0xfb],
+            Di => vec![0xf3],
+
+            In { port } => vec![0xdb, port],
+            Out { port } => vec![0xd3, port],
+
+            Lxi { reg: pair, value } => with_addr(0b00_00_0001 | rp(self, pair)? << 4, value),
+            Stax { ptr } => vec![0b00_00_0010 | ptr_rp(self, ptr)? << 4],
+            Ldax { ptr } => vec![0b00_00_1010 | ptr_rp(self, ptr)? << 4],
+            Inx { reg_pair } => vec![0b00_00_0011 | rp(self, reg_pair)? << 4],
+            Dcx { reg_pair } => vec![0b00_00_1011 | rp(self, reg_pair)? << 4],
+            Dad { reg_pair } => vec![0b00_00_1001 | rp(self, reg_pair)? << 4],
+
+            Shld { addr } => with_addr(0x22, addr),
+            Lhld { addr } => with_addr(0x2a, addr),
+            Sta { addr } => with_addr(0x32, addr),
+            Lda { addr } => with_addr(0x3a, addr),
+
+            Inr { reg: r } => vec![0b00_000_100 | reg(r) << 3],
+            Dcr { reg: r } => vec![0b00_000_101 | reg(r) << 3],
+            Mvi { reg: r, value } => vec![0b00_000_110 | reg(r) << 3, value],
+
+            Rlc => vec![0x07],
+            Rrc => vec![0x0f],
+            Ral => vec![0x17],
+            Rar => vec![0x1f],
+            Daa => vec![0x27],
+            Cma => vec![0x2f],
+            Stc => vec![0x37],
+            Cmc => vec![0x3f],
+
+            Mov { src, dest } => vec![0b01_000_000 | reg(dest) << 3 | reg(src)],
+
+            Add { reg: r } => vec![0b10000_000 | reg(r)],
+            Adc { reg: r } => vec![0b10001_000 | reg(r)],
+            Sub { reg: r } => vec![0b10010_000 | reg(r)],
+            Sbb { reg: r } => vec![0b10011_000 | reg(r)],
+            Ana { reg: r } => vec![0b10100_000 | reg(r)],
+            Xra { reg: r } => vec![0b10101_000 | reg(r)],
+            Ora { reg: r } => vec![0b10110_000 | reg(r)],
+            Cmp { reg: r } => vec![0b10111_000 | reg(r)],
+
+            Adi { value } => vec![0xc6, value],
+            Aci { value } => vec![0xce, value],
+            Sui { value } => vec![0xd6, value],
+            Sbi { value } => vec![0xde, value],
+            Ani { value } => vec![0xe6, value],
+            Xri { value } => vec![0xee, value],
+            Ori { value } => vec![0xf6, value],
+            Cpi { value } => vec![0xfe, value],
+
+            Jmp { addr, condition: None } => with_addr(0xc3, addr),
+            Jmp { addr, condition: Some(cond) } => with_addr(0b11_000_010 | (cond as u8) << 3, addr),
+            Call { addr, condition: None } => with_addr(0xcd, addr),
+            Call { addr, condition: Some(cond) } => with_addr(0b11_000_100 | (cond as u8) << 3, addr),
+            Ret { condition: None } => vec![0xc9],
+            Ret { condition: Some(cond) } => vec![0b11_000_000 | (cond as u8) << 3],
+
+            Pop { reg_pair } => vec![0b11_00_0001 | stack_rp(self, reg_pair)? << 4],
+            Push { reg_pair } => vec![0b11_00_0101 | stack_rp(self, reg_pair)? << 4],
+
+            Xthl => vec![0xe3],
+            Xchg => vec![0xeb],
+            Pchl => vec![0xe9],
+            Sphl => vec![0xf9],
+
+            Rst { index } if index < 8 => vec![0b11_000_111 | index << 3],
+            Rst { .. } => return Err(EncodeError(self.raw_asm())),
+
+            Dsub => vec![0x08],
+            Arhl => vec![0x10],
+            Rdel => vec![0x18],
+            Ldhi { imm } => vec![0x28, imm],
+            Ldsi { imm } => vec![0x38, imm],
+            Rstv => vec![0xcb],
+            Shlx => vec![0xd9],
+            Lhlx => vec![0xed],
+            Jnk { addr } => with_addr(0xdd, addr),
+            Jk { addr } => with_addr(0xfd, addr),
+        };
+
+        Ok(bytes)
+    }
+}
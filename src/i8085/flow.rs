@@ -0,0 +1,21 @@
+use super::Instruction;
+use crate::flow::Flow;
+
+impl Instruction {
+    pub fn flow(&self) -> Flow {
+        use Instruction::*;
+        match *self {
+            Jmp { addr, condition: None } => Flow::Branch { target: addr },
+            Jmp { addr, condition: Some(_) } => Flow::ConditionalBranch { target: addr },
+            Jk { addr } | Jnk { addr } => Flow::ConditionalBranch { target: addr },
+            Call { addr, .. } => Flow::Call { target: addr },
+            Ret { condition: None } => Flow::Return,
+            Ret { condition: Some(_) } => Flow::ConditionalReturn,
+            Pchl => Flow::Indirect,
+            Hlt => Flow::Halt,
+            Rst { index } => Flow::Trap { vector: index as u16 * 8 },
+            Rstv => Flow::Trap { vector: 0x40 },
+            _ => Flow::Fallthrough,
+        }
+    }
+}
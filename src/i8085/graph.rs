@@ -0,0 +1,218 @@
+//! Rendering [`Cfg`]s as Graphviz DOT or Mermaid, for pasting into docs.
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::cfg::{Cfg, EdgeKind};
+use super::Flow;
+use crate::printer::{BankedAddress, LabelKind};
+use crate::symbols::Symbols;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GraphFormat, String> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(format!("unknown graph format {:?} (expected dot or mermaid)", s)),
+        }
+    }
+}
+
+/// How an edge is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    True,
+    False,
+    Call,
+}
+
+pub struct GraphWriter<'a> {
+    cfg: &'a Cfg,
+    format: GraphFormat,
+    calls: bool,
+    symbols: Symbols,
+}
+
+fn node_id(at: BankedAddress) -> String {
+    match at.bank {
+        Some(bank) => format!("b_{:02x}_{:04x}", bank, at.addr),
+        None => format!("b_{:04x}", at.addr),
+    }
+}
+
+impl<'a> GraphWriter<'a> {
+    pub fn new(cfg: &'a Cfg, format: GraphFormat) -> GraphWriter<'a> {
+        GraphWriter { cfg, format, calls: false, symbols: Symbols::default() }
+    }
+
+    /// Draw calls out of a function's blocks, to a node for each callee.
+    pub fn with_calls(mut self) -> GraphWriter<'a> {
+        self.calls = true;
+        self
+    }
+
+    /// Name functions from `symbols` where possible.
+    pub fn with_symbols(mut self, symbols: Symbols) -> GraphWriter<'a> {
+        self.symbols = symbols;
+        self
+    }
+
+    fn function_name(&self, entry: BankedAddress) -> String {
+        match self.symbols.name(entry) {
+            Some(name) => name.to_string(),
+            None => LabelKind::Subroutine.name(entry),
+        }
+    }
+
+    fn begin<W>(&self, w: &mut W, name: &str) -> io::Result<()> where W: Write {
+        match self.format {
+            GraphFormat::Dot => {
+                writeln!(w, "digraph \"{}\" {{", name)?;
+                writeln!(w, "    node [shape=box, fontname=monospace];")
+            }
+            GraphFormat::Mermaid => {
+                writeln!(w, "%% {}", name)?;
+                writeln!(w, "flowchart TD")
+            }
+        }
+    }
+
+    fn end<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        match self.format {
+            GraphFormat::Dot => writeln!(w, "}}"),
+            GraphFormat::Mermaid => Ok(()),
+        }
+    }
+
+    fn node<W>(&self, w: &mut W, id: &str, lines: &[String]) -> io::Result<()> where W: Write {
+        match self.format {
+            GraphFormat::Dot => {
+                let label: String = lines.iter()
+                    .map(|line| format!("{}\\l", line.replace('\\', "\\\\").replace('"', "\\\"")))
+                    .collect();
+                writeln!(w, "    {} [label=\"{}\"];", id, label)
+            }
+            GraphFormat::Mermaid => {
+                let label: Vec<String> = lines.iter().map(|line| line.replace('"', "#quot;")).collect();
+                writeln!(w, "    {}[\"{}\"]", id, label.join("<br/>"))
+            }
+        }
+    }
+
+    /// Writes an edge; Mermaid styles edges by their index, which `n` counts.
+    fn edge<W>(&self, w: &mut W, from: &str, to: &str, style: Style, n: &mut usize) -> io::Result<()> where W: Write {
+        match self.format {
+            GraphFormat::Dot => {
+                let attrs = match style {
+                    Style::Plain => "",
+                    Style::True => " [color=green, label=\"true\"]",
+                    Style::False => " [color=red, label=\"false\"]",
+                    Style::Call => " [style=dashed]",
+                };
+                writeln!(w, "    {} -> {}{};", from, to, attrs)?;
+            }
+            GraphFormat::Mermaid => {
+                match style {
+                    Style::Plain => writeln!(w, "    {} --> {}", from, to)?,
+                    Style::True => writeln!(w, "    {} -->|true| {}", from, to)?,
+                    Style::False => writeln!(w, "    {} -->|false| {}", from, to)?,
+                    Style::Call => writeln!(w, "    {} -.-> {}", from, to)?,
+                }
+                match style {
+                    Style::True => writeln!(w, "    linkStyle {} stroke:green", n)?,
+                    Style::False => writeln!(w, "    linkStyle {} stroke:red", n)?,
+                    _ => {}
+                }
+            }
+        }
+        *n += 1;
+        Ok(())
+    }
+
+    /// Draws the function entered at `entry`, a node per block.
+    pub fn function<W>(&self, w: &mut W, entry: BankedAddress) -> io::Result<()> where W: Write {
+        let function = match self.cfg.function(entry) {
+            Some(function) => function,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("no function at {}", entry))),
+        };
+        self.begin(w, &self.function_name(entry))?;
+
+        for &start in &function.blocks {
+            let block = self.cfg.block(start).expect("functions are made of blocks");
+            let lines: Vec<String> = block.instructions.iter()
+                .map(|(at, instr)| format!("{}: {}", at, instr.raw_asm()))
+                .collect();
+            self.node(w, &node_id(start), &lines)?;
+        }
+
+        let mut callees = BTreeSet::new();
+        let mut n = 0;
+        for &start in &function.blocks {
+            let conditional = matches!(self.cfg.block(start).map(|b| b.last().flow()), Some(Flow::ConditionalBranch { .. }));
+            for edge in self.cfg.successors(start) {
+                let style = match edge.kind {
+                    EdgeKind::Taken if conditional => Style::True,
+                    EdgeKind::NotTaken if conditional => Style::False,
+                    EdgeKind::Fallthrough | EdgeKind::Taken | EdgeKind::NotTaken => Style::Plain,
+                    EdgeKind::Call if self.calls => Style::Call,
+                    EdgeKind::Call | EdgeKind::Return => continue,
+                };
+                // calls, and jumps to another function's entry, leave the function
+                let to = if style == Style::Call || !function.blocks.contains(&edge.to) {
+                    callees.insert(edge.to);
+                    format!("f_{}", node_id(edge.to))
+                } else {
+                    node_id(edge.to)
+                };
+                self.edge(w, &node_id(start), &to, style, &mut n)?;
+            }
+        }
+        for callee in callees {
+            self.node(w, &format!("f_{}", node_id(callee)), &[self.function_name(callee)])?;
+        }
+
+        self.end(w)
+    }
+
+    /// Draws which functions call which. Calls from outside any function come from a node
+    /// named `(top level)`.
+    pub fn call_graph<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        self.begin(w, "calls")?;
+
+        let mut calls = BTreeSet::new();
+        for edge in self.cfg.edges().filter(|e| e.kind == EdgeKind::Call) {
+            let callers: Vec<BankedAddress> = self.cfg.functions()
+                .filter(|f| f.blocks.contains(&edge.from))
+                .map(|f| f.entry)
+                .collect();
+            if callers.is_empty() {
+                calls.insert((None, edge.to));
+            }
+            calls.extend(callers.into_iter().map(|caller| (Some(caller), edge.to)));
+        }
+
+        if calls.iter().any(|(caller, _)| caller.is_none()) {
+            self.node(w, "top", &["(top level)".to_string()])?;
+        }
+        for function in self.cfg.functions() {
+            self.node(w, &node_id(function.entry), &[self.function_name(function.entry)])?;
+        }
+        let mut n = 0;
+        for (caller, callee) in calls {
+            let from = caller.map_or("top".to_string(), node_id);
+            self.edge(w, &from, &node_id(callee), Style::Plain, &mut n)?;
+        }
+
+        self.end(w)
+    }
+}
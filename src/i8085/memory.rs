@@ -1,41 +1,217 @@
-use bitflags::bitflags;
-use num_enum::TryFromPrimitive;
+//! Bank-switched memory maps, described as data: a selector register written through a port
+//! or address, and windows whose bank is picked by a bit field of its value.
+
 use std::convert::TryFrom;
-// TODO: make (more) generic (ie, remove the PC-8300 specific parts)
+use std::error::Error;
+use std::fmt;
+
+use crate::memory::{MemoryImage, Region, RegionKind};
+use crate::printer::{BankId, BankedAddress};
+
+/// Where the bank selector is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selector {
+    /// By `out port`
+    Port(u8),
+    /// By a store to a memory-mapped latch
+    Address(u16),
+}
+
+/// `width` bits of the selector value, starting at bit `shift`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    pub shift: u8,
+    pub width: u8,
+}
 
-bitflags! {
-    pub struct BankFlags: u8 {
-        const HADR2 = 1 << 3;
-        const HADR1 = 1 << 2;
-        const LADR2 = 1 << 1;
-        const LADR1 = 1 << 0;
+impl BitField {
+    pub fn new(shift: u8, width: u8) -> BitField {
+        BitField { shift, width }
+    }
+
+    /// The selector bits the field covers.
+    pub fn mask(&self) -> u8 {
+        (((1u16 << self.width) - 1) << self.shift) as u8
+    }
+
+    pub fn extract(&self, value: u8) -> u8 {
+        (value & self.mask()) >> self.shift
     }
 }
 
-#[derive(Debug, TryFromPrimitive)]
-#[repr(u8)]
-pub enum LowBank {
-    Rom0 = 0b00,
-    Rom1 = 0b01,
-    Ram2 = 0b10,
-    Ram3 = 0b11,
+/// Something that can be switched into a window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bank {
+    pub name: String,
+    pub kind: RegionKind,
+    pub len: usize,
+    /// Where its contents start in the image file, if they're there at all.
+    pub file_offset: Option<usize>,
 }
 
-#[derive(Debug, TryFromPrimitive)]
-#[repr(u8)]
-pub enum HighBank {
-    StdRam = 0b00,
-    Unused = 0b01,
-    Ram2 = 0b10,
-    Ram3 = 0b11,
+impl Bank {
+    pub fn rom(name: &str, len: usize, file_offset: usize) -> Bank {
+        Bank { name: name.to_string(), kind: RegionKind::Rom, len, file_offset: Some(file_offset) }
+    }
+
+    pub fn ram(name: &str, len: usize) -> Bank {
+        Bank { name: name.to_string(), kind: RegionKind::Ram, len, file_offset: None }
+    }
+}
+
+/// A range of the address space showing one of several banks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub base: u16,
+    pub len: usize,
+    pub field: BitField,
+    /// The bank shown for each value of `field`, by name; `None` leaves the window unmapped.
+    pub banks: Vec<Option<String>>,
 }
 
-impl BankFlags {
-    pub fn low(&self) -> LowBank {
-        LowBank::try_from((*self & (BankFlags::LADR1 | BankFlags::LADR2)).bits()).unwrap()
+impl Window {
+    pub fn new(base: u16, len: usize, field: BitField, banks: &[Option<&str>]) -> Window {
+        let banks = banks.iter().map(|bank| bank.map(str::to_string)).collect();
+        Window { base, len, field, banks }
+    }
+
+    /// A window which always shows `bank`.
+    pub fn fixed(base: u16, len: usize, bank: &str) -> Window {
+        Window::new(base, len, BitField::new(0, 0), &[Some(bank)])
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        (self.base as usize..self.base as usize + self.len).contains(&(addr as usize))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankMapError(pub String);
+
+impl fmt::Display for BankMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for BankMapError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankMap {
+    pub selector: Selector,
+    pub banks: Vec<Bank>,
+    pub windows: Vec<Window>,
+}
+
+impl BankMap {
+    /// The PC-8300's layout: bits 0-1 of the selector pick what's in the low 32K, bits 2-3
+    /// the high 32K, and the two extra RAMs can appear in either. Which port or latch the
+    /// selector sits at isn't part of the layout, so it's given here.
+    pub fn pc8300(selector: Selector) -> BankMap {
+        BankMap {
+            selector,
+            banks: vec![
+                Bank::rom("rom0", 0x8000, 0x0000),
+                Bank::rom("rom1", 0x8000, 0x8000),
+                Bank::ram("ram2", 0x8000),
+                Bank::ram("ram3", 0x8000),
+                Bank::ram("stdram", 0x8000),
+            ],
+            windows: vec![
+                Window::new(0x0000, 0x8000, BitField::new(0, 2), &[Some("rom0"), Some("rom1"), Some("ram2"), Some("ram3")]),
+                Window::new(0x8000, 0x8000, BitField::new(2, 2), &[Some("stdram"), None, Some("ram2"), Some("ram3")]),
+            ],
+        }
+    }
+
+    /// Checks that windows don't overlap, have a bank (or `None`) for every value of their
+    /// field, and only name banks which exist and are big enough.
+    pub fn validate(&self) -> Result<(), BankMapError> {
+        let err = |message: String| Err(BankMapError(message));
+        for (i, window) in self.windows.iter().enumerate() {
+            let end = window.base as usize + window.len;
+            if end > 0x10000 {
+                return err(format!("window at {:#06x} runs past 0xffff", window.base));
+            }
+            let overlaps = |w: &&Window| (w.base as usize) < end && (window.base as usize) < w.base as usize + w.len;
+            if let Some(other) = self.windows[..i].iter().find(overlaps) {
+                return err(format!("windows at {:#06x} and {:#06x} overlap", other.base, window.base));
+            }
+            if window.field.shift + window.field.width > 8 {
+                return err(format!("window at {:#06x} selects with bits past the selector's 8", window.base));
+            }
+            if window.banks.len() != 1 << window.field.width {
+                return err(format!("window at {:#06x} needs {} banks for its {}-bit field, has {}",
+                    window.base, 1 << window.field.width, window.field.width, window.banks.len()));
+            }
+            for name in window.banks.iter().flatten() {
+                match self.bank(name) {
+                    None => return err(format!("window at {:#06x} names unknown bank `{}`", window.base, name)),
+                    Some(bank) if bank.len < window.len =>
+                        return err(format!("bank `{}` is smaller than the window at {:#06x}", name, window.base)),
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn bank(&self, name: &str) -> Option<&Bank> {
+        self.banks.iter().find(|bank| bank.name == name)
+    }
+
+    pub fn id(&self, name: &str) -> Option<BankId> {
+        self.banks.iter().position(|bank| bank.name == name).map(|i| i as BankId)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.banks.iter().map(|bank| bank.name.clone()).collect()
+    }
+
+    pub fn window(&self, addr: u16) -> Option<&Window> {
+        self.windows.iter().find(|window| window.contains(addr))
+    }
+
+    /// The bank `window` shows when the selector holds `selector`.
+    pub fn selected(&self, window: &Window, selector: u8) -> Option<&Bank> {
+        let name = window.banks.get(window.field.extract(selector) as usize)?.as_ref()?;
+        self.bank(name)
+    }
+
+    /// Which bank `addr` falls in when the selector holds `selector`, and where in it.
+    pub fn resolve(&self, addr: u16, selector: u8) -> Option<(&Bank, usize)> {
+        let window = self.window(addr)?;
+        let bank = self.selected(window, selector)?;
+        Some((bank, (addr - window.base) as usize))
+    }
+
+    /// Where `at` comes from in the image file, if it's in a bank which is.
+    pub fn file_offset(&self, at: BankedAddress) -> Option<usize> {
+        let bank = self.banks.get(at.bank? as usize)?;
+        let addr = u16::try_from(at.addr).ok()?;
+        let window = self.windows.iter()
+            .find(|w| w.contains(addr) && w.banks.iter().flatten().any(|name| *name == bank.name))?;
+        Some(bank.file_offset? + (addr - window.base) as usize)
     }
 
-    pub fn high(&self) -> HighBank {
-        HighBank::try_from((*self & (BankFlags::HADR1 | BankFlags::HADR2)).bits()).unwrap()
+    /// What the CPU sees when the selector holds `selector`, with ROM contents taken from
+    /// `file`. Addresses outside every window are unmapped.
+    pub fn mapping(&self, file: &[u8], selector: u8) -> MemoryImage {
+        let mut mem = MemoryImage::default();
+        for window in &self.windows {
+            let bank = match self.selected(window, selector) {
+                Some(bank) => bank,
+                None => continue,
+            };
+            let data = match bank.file_offset {
+                Some(offset) => file.get(offset..).unwrap_or_default().iter().take(window.len).copied().collect(),
+                None => Vec::new(),
+            };
+            let readable = bank.kind != RegionKind::Mmio;
+            let region = Region { kind: bank.kind, base: window.base as usize, len: window.len, data, readable };
+            // if windows overlap (see validate), the first one wins
+            mem.map(region).ok();
+        }
+        mem
     }
 }
@@ -1,14 +1,63 @@
 use std::fmt;
 use std::io::{self, Write};
+use std::str::FromStr;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-use crate::printer::Print;
+use crate::printer::{Address, LabelKind, Operands, Print};
+use crate::symbols::Symbols;
+use crate::syntax::Syntax;
 
 mod decode;
-mod trace;
-mod memory;
+mod encode;
+mod parse;
+mod flow;
+mod dataflow;
+mod operand;
+mod syntax;
+pub mod cfg;
+pub mod graph;
+pub mod timing;
+pub mod trace;
+pub mod memory;
+
+pub use decode::DecodeError;
+pub use encode::EncodeError;
+pub use parse::ParseError;
+pub use crate::flow::Flow;
+pub use operand::Operand;
+pub use dataflow::{Effects, Flags, MemRef, Regs};
+pub use timing::Cycles;
+pub use trace::{Tracer, Trace};
+pub use cfg::{Block, Cfg, Edge, EdgeKind, Function};
+pub use graph::{GraphFormat, GraphWriter};
+
+/// Which processor the code runs on, which decides how some opcodes decode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cpu {
+    /// No RIM/SIM; the 8085's undocumented opcodes are aliases of NOP, JMP, CALL and RET.
+    I8080,
+    /// Only the documented 8085 instructions.
+    I8085,
+    /// The 8085 including its undocumented instructions.
+    #[default]
+    I8085Undoc,
+}
+
+impl FromStr for Cpu {
+    type Err = String;
 
-#[derive(Debug, IntoPrimitive, TryFromPrimitive)]
+    /// Takes AS's names: `8080`, `8085` and `8085undoc`.
+    fn from_str(s: &str) -> Result<Cpu, String> {
+        match s.to_lowercase().as_str() {
+            "8080" => Ok(Cpu::I8080),
+            "8085" => Ok(Cpu::I8085),
+            "8085undoc" => Ok(Cpu::I8085Undoc),
+            _ => Err(format!("unknown cpu {:?} (expected 8080, 8085 or 8085undoc)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Register {
     A = 0b111,
@@ -22,7 +71,7 @@ pub enum Register {
     Mem = 0b110,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum RegisterPair {
     BC, // 00
@@ -32,7 +81,7 @@ pub enum RegisterPair {
     PSW, // 11
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ConditionCodes {
     NZ = 0b000,
@@ -45,6 +94,30 @@ pub enum ConditionCodes {
     M = 0b111,
 }
 
+impl fmt::Display for Cpu {
+    /// AS's name, as [`FromStr`] takes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cpu::I8080 => write!(f, "8080"),
+            Cpu::I8085 => write!(f, "8085"),
+            Cpu::I8085Undoc => write!(f, "8085undoc"),
+        }
+    }
+}
+
+impl RegisterPair {
+    /// Intel's name, as assemblers take it: `b`, `d` and `h` rather than `bc`, `de` and `hl`.
+    pub fn intel_name(&self) -> &'static str {
+        match self {
+            RegisterPair::BC => "b",
+            RegisterPair::DE => "d",
+            RegisterPair::HL => "h",
+            RegisterPair::SP => "sp",
+            RegisterPair::PSW => "psw",
+        }
+    }
+}
+
 impl fmt::Display for RegisterPair {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use RegisterPair::*;
@@ -91,7 +164,7 @@ impl fmt::Display for ConditionCodes {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
     Nop,
     Hlt,
@@ -185,108 +258,56 @@ pub enum Instruction {
 
 impl Instruction {
     pub fn raw_asm(&self) -> String {
-        use Instruction::*;
-        match self {
-            Nop => format!("nop"),
-            Hlt => format!("hlt"),
-            Xthl => format!("xthl"),
-            Xchg => format!("xchg"),
-            Pchl => format!("pchl"),
-            Sphl => format!("sphl"),
-
-            Rst {index} => format!("rst {:#x}", index),
-            Rstv => format!("rstv"),
-
-            Dsub => format!("dsub"),
-            Arhl => format!("arhl"),
-            Rdel => format!("rdel"),
-            Shlx => format!("shlx"),
-            Lhlx => format!("lhlx"),
-
-            Rlc => format!("rlc"),
-            Ral => format!("ral"),
-            Rrc => format!("rrc"),
-            Rar => format!("rar"),
-            Ei => format!("ei"),
-            Di => format!("di"),
-
-            Daa => format!("daa"),
-            Stc => format!("stc"),
-            Cma => format!("cma"),
-            Cmc => format!("cmc"),
-            Rim => format!("rim"),
-            Sim => format!("sim"),
-
-            Ldhi { imm } => format!("ldhi {:#x}", imm),
-            Ldsi { imm } => format!("ldsi {:#x}", imm),
-
-            Jnk { addr } => format!("jnk {:#x}", addr),
-            Jk { addr } => format!("jk {:#x}", addr),
-
-            Mov { src, dest } => format!("mov {}, {}", dest, src),
-
-            Adi { value } => format!("adi {:#x}", value),
-            Aci { value } => format!("aci {:#x}", value),
-            Sui { value } => format!("sui {:#x}", value),
-            Sbi { value } => format!("sbi {:#x}", value),
-            Ani { value } => format!("ani {:#x}", value),
-            Ori { value } => format!("ori {:#x}", value),
-            Xri { value } => format!("xri {:#x}", value),
-            Cpi { value } => format!("cpi {:#x}", value),
-
-            Add { reg } => format!("add {}", reg),
-            Adc { reg } => format!("adc {}", reg),
-            Sub { reg } => format!("sub {}", reg),
-            Sbb { reg } => format!("sbb {}", reg),
-            Ana { reg } => format!("ana {}", reg),
-            Ora { reg } => format!("ora {}", reg),
-            Xra { reg } => format!("xra {}", reg),
-            Cmp { reg } => format!("cmp {}", reg),
-
-            Pop { reg_pair } => format!("pop {}", reg_pair),
-            Push { reg_pair } => format!("push {}", reg_pair),
-
-            Stax { ptr } => format!("stax {}", ptr),
-            Ldax { ptr } => format!("ldax {}", ptr),
-
-            Inx { reg_pair } => format!("inx {}", reg_pair),
-            Dcx { reg_pair } => format!("dcx {}", reg_pair),
-
-
-            Inr { reg } => format!("inr {}", reg),
-            Dcr { reg } => format!("dcr {}", reg),
-
-            Lxi { reg, value } => format!("lxi {}, {:#x}", reg, value),
-            Mvi { reg, value } => format!("mvi {}, {:#x}", reg, value),
-
-            Dad { reg_pair } => format!("dad {}", reg_pair),
-
-            In { port } => format!("in {:#x}", port),
-            Out { port } => format!("out {:#x}", port),
-
-            Lda { addr } => format!("lda {:#x}", addr),
-            Sta { addr } => format!("sta {:#x}", addr),
-            Lhld { addr } => format!("lhld {:#x}", addr),
-            Shld { addr } => format!("shld {:#x}", addr),
-
-            Jmp { addr, condition } => match condition {
-                None => format!("jmp {:#x}", addr),
-                Some(cond) => format!("j{} {:#x}", cond, addr),
-            },
-            Call { addr, condition } => match condition {
-                None => format!("call {:#x}", addr),
-                Some(cond) => format!("c{} {:#x}", cond, addr),
-            },
-            Ret { condition } => match condition {
-                None => format!("ret"),
-                Some(cond) => format!("r{}", cond),
-            },
-        }
+        self.asm_with(&Symbols::default())
+    }
+
+    /// Renders as [`Instruction::raw_asm`] does, but naming addresses and ports from
+    /// `symbols`.
+    pub fn asm_with(&self, symbols: &Symbols) -> String {
+        self.asm_in(Syntax::Raw, symbols)
+    }
+
+    /// Renders as [`Instruction::asm_with`] does, but with Intel's register pair names, so
+    /// an assembler will take it.
+    pub fn intel_asm_with(&self, symbols: &Symbols) -> String {
+        self.asm_in(Syntax::Intel, symbols)
     }
 }
 
 impl Print for Instruction {
+    type Operand = Operand;
+    type Cpu = Cpu;
+
     fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
         write!(w, "{}", self.raw_asm())
     }
+
+    fn print_labelled<W>(&self, w: &mut W, symbols: &Symbols) -> io::Result<()> where W: Write {
+        write!(w, "{}", self.asm_with(symbols))
+    }
+
+    fn print_syntax<W>(&self, w: &mut W, symbols: &Symbols, syntax: Syntax) -> io::Result<()> where W: Write {
+        write!(w, "{}", self.asm_in(syntax, symbols))
+    }
+
+    fn code(&self) -> Option<(Operands<Operand>, Flow)> {
+        Some((self.operands().collect(), self.flow()))
+    }
+
+    fn references(&self) -> Vec<(Address, LabelKind)> {
+        let kind = match (self, self.flow()) {
+            (Instruction::Rst { index }, _) => LabelKind::Vector(*index),
+            (_, Flow::Call { .. }) | (_, Flow::Trap { .. }) => LabelKind::Subroutine,
+            _ => LabelKind::Location,
+        };
+        self.flow().target().map(|target| (target as Address, kind)).into_iter().collect()
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.encode().unwrap_or_default()
+    }
+
+    fn timing(&self, cpu: Cpu) -> Option<String> {
+        Some(self.cycles(cpu).to_string())
+    }
 }
@@ -0,0 +1,93 @@
+use crate::printer::Address;
+use crate::symbols::{SymbolKind, Symbols};
+use super::{Instruction, Register, RegisterPair};
+
+/// An operand of an instruction, as written in Intel's syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    RegisterPair(RegisterPair),
+    Imm8(u8),
+    /// LXI's, which may be an address or just a number
+    Imm16(u16),
+    /// A jump or call target
+    Code(u16),
+    /// An address read or written
+    Data(u16),
+    Port(u8),
+    /// RST's vector number; it calls 8 times that
+    Vector(u8),
+}
+
+impl Operand {
+    /// The number this stands for, if it's not a register.
+    pub fn value(&self) -> Option<u16> {
+        match *self {
+            Operand::Register(_) | Operand::RegisterPair(_) => None,
+            Operand::Imm8(value) | Operand::Port(value) | Operand::Vector(value) => Some(value as u16),
+            Operand::Imm16(value) | Operand::Code(value) | Operand::Data(value) => Some(value),
+        }
+    }
+
+    /// The address this may refer to: code and data addresses, 16-bit immediates, and where
+    /// RST calls.
+    pub fn address(&self) -> Option<u16> {
+        match *self {
+            Operand::Imm16(addr) | Operand::Code(addr) | Operand::Data(addr) => Some(addr),
+            Operand::Vector(index) => Some(index as u16 * 8),
+            _ => None,
+        }
+    }
+
+    /// What `symbols` call this. 16-bit immediates are only named after data symbols, since
+    /// they're as likely to be counts as pointers.
+    pub fn name(&self, symbols: &Symbols) -> Option<String> {
+        match *self {
+            Operand::Code(addr) | Operand::Data(addr) => symbols.name(addr as Address).map(str::to_string),
+            Operand::Port(port) => symbols.port(port as Address).map(str::to_string),
+            Operand::Imm16(value) => symbols.symbol(value as Address)
+                .filter(|sym| sym.kind == SymbolKind::Data)
+                .map(|sym| sym.name.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Instruction {
+    /// The operands, in Intel's order, each with its byte offset in the encoding. Registers
+    /// are encoded in the opcode, at 0.
+    pub fn operands(&self) -> impl Iterator<Item = (usize, Operand)> {
+        use Instruction::*;
+        use Operand::{Code, Data, Imm16, Imm8, Port, Vector};
+        let reg = |reg: &Register| (0, Operand::Register(*reg));
+        let pair = |pair: &RegisterPair| (0, Operand::RegisterPair(*pair));
+        let operands = match self {
+            Mov { src, dest } => vec![reg(dest), reg(src)],
+            Mvi { reg: r, value } => vec![reg(r), (1, Imm8(*value))],
+            Inr { reg: r } | Dcr { reg: r }
+            | Add { reg: r } | Adc { reg: r } | Sub { reg: r } | Sbb { reg: r }
+            | Ana { reg: r } | Ora { reg: r } | Xra { reg: r } | Cmp { reg: r } => vec![reg(r)],
+
+            Lxi { reg: p, value } => vec![pair(p), (1, Imm16(*value))],
+            Stax { ptr: p } | Ldax { ptr: p }
+            | Inx { reg_pair: p } | Dcx { reg_pair: p } | Dad { reg_pair: p }
+            | Pop { reg_pair: p } | Push { reg_pair: p } => vec![pair(p)],
+
+            Adi { value } | Aci { value } | Sui { value } | Sbi { value }
+            | Ani { value } | Ori { value } | Xri { value } | Cpi { value } => vec![(1, Imm8(*value))],
+            Ldhi { imm } | Ldsi { imm } => vec![(1, Imm8(*imm))],
+
+            In { port } | Out { port } => vec![(1, Port(*port))],
+
+            Lda { addr } | Sta { addr } | Lhld { addr } | Shld { addr } => vec![(1, Data(*addr))],
+            Jmp { addr, .. } | Call { addr, .. } | Jk { addr } | Jnk { addr } => vec![(1, Code(*addr))],
+
+            Rst { index } => vec![(0, Vector(*index))],
+
+            Nop | Hlt | Rlc | Ral | Rrc | Rar | Ei | Di | Daa | Stc | Cma | Cmc | Rim | Sim
+            | Ret { .. } | Xthl | Xchg | Pchl | Sphl | Rstv
+            | Dsub | Arhl | Rdel | Shlx | Lhlx => Vec::new(),
+        };
+        operands.into_iter()
+    }
+}
@@ -0,0 +1,269 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{ConditionCodes, Instruction, Register, RegisterPair};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    UnknownMnemonic(String),
+    BadOperand(String),
+    WrongOperandCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "no instruction"),
+            ParseError::UnknownMnemonic(m) => write!(f, "unknown mnemonic `{}`", m),
+            ParseError::BadOperand(o) => write!(f, "bad operand `{}`", o),
+            ParseError::WrongOperandCount { expected, found } =>
+                write!(f, "expected {} operands, found {}", expected, found),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+fn register(s: &str) -> Result<Register, ParseError> {
+    match s {
+        "a" => Ok(Register::A),
+        "b" => Ok(Register::B),
+        "c" => Ok(Register::C),
+        "d" => Ok(Register::D),
+        "e" => Ok(Register::E),
+        "h" => Ok(Register::H),
+        "l" => Ok(Register::L),
+        "m" => Ok(Register::Mem),
+        _ => Err(ParseError::BadOperand(s.to_string())),
+    }
+}
+
+/// Accepts both our names (`bc`) and Intel's (`b`).
+fn register_pair(s: &str) -> Result<RegisterPair, ParseError> {
+    match s {
+        "bc" | "b" => Ok(RegisterPair::BC),
+        "de" | "d" => Ok(RegisterPair::DE),
+        "hl" | "h" => Ok(RegisterPair::HL),
+        "sp" => Ok(RegisterPair::SP),
+        "psw" => Ok(RegisterPair::PSW),
+        _ => Err(ParseError::BadOperand(s.to_string())),
+    }
+}
+
+fn condition(s: &str) -> Option<ConditionCodes> {
+    use ConditionCodes::*;
+    match s {
+        "nz" => Some(NZ),
+        "z" => Some(Z),
+        "nc" => Some(NC),
+        "c" => Some(C),
+        "po" => Some(PO),
+        "pe" => Some(PE),
+        "p" => Some(P),
+        "m" => Some(M),
+        _ => None,
+    }
+}
+
+/// Parses `0x1337`, `$1337`, `1337h` or decimal `4919`.
+fn number(s: &str) -> Result<u16, ParseError> {
+    let bad = || ParseError::BadOperand(s.to_string());
+    let parsed = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(hex) = s.strip_suffix('h') {
+        u16::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    };
+    parsed.map_err(|_| bad())
+}
+
+fn byte(s: &str) -> Result<u8, ParseError> {
+    let n = number(s)?;
+    if n > 0xff {
+        return Err(ParseError::BadOperand(s.to_string()));
+    }
+    Ok(n as u8)
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    /// Parses a line in the syntax [`Instruction::raw_asm`] produces, eg. `lxi hl, 0x1337`.
+    /// Comments after a `;` are ignored.
+    fn from_str(line: &str) -> Result<Instruction, ParseError> {
+        use Instruction::*;
+
+        let line = line.split(';').next().unwrap_or("").trim().to_lowercase();
+        let (mnemonic, rest) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (&line[..], ""),
+        };
+        if mnemonic.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let operands: Vec<&str> = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(str::trim).collect()
+        };
+
+        let expect = |expected: usize| {
+            if operands.len() == expected {
+                Ok(())
+            } else {
+                Err(ParseError::WrongOperandCount { expected, found: operands.len() })
+            }
+        };
+
+        let instr = match mnemonic {
+            "nop" | "hlt" | "xthl" | "xchg" | "pchl" | "sphl" | "rstv" | "dsub" | "arhl"
+            | "rdel" | "shlx" | "lhlx" | "rlc" | "ral" | "rrc" | "rar" | "ei" | "di"
+            | "daa" | "stc" | "cma" | "cmc" | "rim" | "sim" | "ret" => {
+                expect(0)?;
+                match mnemonic {
+                    "nop" => Nop,
+                    "hlt" => Hlt,
+                    "xthl" => Xthl,
+                    "xchg" => Xchg,
+                    "pchl" => Pchl,
+                    "sphl" => Sphl,
+                    "rstv" => Rstv,
+                    "dsub" => Dsub,
+                    "arhl" => Arhl,
+                    "rdel" => Rdel,
+                    "shlx" => Shlx,
+                    "lhlx" => Lhlx,
+                    "rlc" => Rlc,
+                    "ral" => Ral,
+                    "rrc" => Rrc,
+                    "rar" => Rar,
+                    "ei" => Ei,
+                    "di" => Di,
+                    "daa" => Daa,
+                    "stc" => Stc,
+                    "cma" => Cma,
+                    "cmc" => Cmc,
+                    "rim" => Rim,
+                    "sim" => Sim,
+                    _ => Ret { condition: None },
+                }
+            }
+
+            "rst" => {
+                expect(1)?;
+                let index = byte(operands[0])?;
+                if index > 7 {
+                    return Err(ParseError::BadOperand(operands[0].to_string()));
+                }
+                Rst { index }
+            }
+
+            "ldhi" | "ldsi" | "adi" | "aci" | "sui" | "sbi" | "ani" | "ori" | "xri" | "cpi"
+            | "in" | "out" => {
+                expect(1)?;
+                let value = byte(operands[0])?;
+                match mnemonic {
+                    "ldhi" => Ldhi { imm: value },
+                    "ldsi" => Ldsi { imm: value },
+                    "adi" => Adi { value },
+                    "aci" => Aci { value },
+                    "sui" => Sui { value },
+                    "sbi" => Sbi { value },
+                    "ani" => Ani { value },
+                    "ori" => Ori { value },
+                    "xri" => Xri { value },
+                    "cpi" => Cpi { value },
+                    "in" => In { port: value },
+                    _ => Out { port: value },
+                }
+            }
+
+            // jx5/jnx5 are AS's names for jk/jnk
+            "jnk" | "jnx5" | "jk" | "jx5" | "lda" | "sta" | "lhld" | "shld" | "jmp" | "call" => {
+                expect(1)?;
+                let addr = number(operands[0])?;
+                match mnemonic {
+                    "jnk" | "jnx5" => Jnk { addr },
+                    "jk" | "jx5" => Jk { addr },
+                    "lda" => Lda { addr },
+                    "sta" => Sta { addr },
+                    "lhld" => Lhld { addr },
+                    "shld" => Shld { addr },
+                    "jmp" => Jmp { addr, condition: None },
+                    _ => Call { addr, condition: None },
+                }
+            }
+
+            "mov" => {
+                expect(2)?;
+                Mov { dest: register(operands[0])?, src: register(operands[1])? }
+            }
+            "mvi" => {
+                expect(2)?;
+                Mvi { reg: register(operands[0])?, value: byte(operands[1])? }
+            }
+            "lxi" => {
+                expect(2)?;
+                Lxi { reg: register_pair(operands[0])?, value: number(operands[1])? }
+            }
+
+            "add" | "adc" | "sub" | "sbb" | "ana" | "ora" | "xra" | "cmp" | "inr" | "dcr" => {
+                expect(1)?;
+                let reg = register(operands[0])?;
+                match mnemonic {
+                    "add" => Add { reg },
+                    "adc" => Adc { reg },
+                    "sub" => Sub { reg },
+                    "sbb" => Sbb { reg },
+                    "ana" => Ana { reg },
+                    "ora" => Ora { reg },
+                    "xra" => Xra { reg },
+                    "cmp" => Cmp { reg },
+                    "inr" => Inr { reg },
+                    _ => Dcr { reg },
+                }
+            }
+
+            "pop" | "push" | "stax" | "ldax" | "inx" | "dcx" | "dad" => {
+                expect(1)?;
+                let reg_pair = register_pair(operands[0])?;
+                match mnemonic {
+                    "pop" => Pop { reg_pair },
+                    "push" => Push { reg_pair },
+                    "stax" => Stax { ptr: reg_pair },
+                    "ldax" => Ldax { ptr: reg_pair },
+                    "inx" => Inx { reg_pair },
+                    "dcx" => Dcx { reg_pair },
+                    _ => Dad { reg_pair },
+                }
+            }
+
+            _ => {
+                let mut chars = mnemonic.chars();
+                let kind = chars.next();
+                let condition = condition(chars.as_str())
+                    .ok_or_else(|| ParseError::UnknownMnemonic(mnemonic.to_string()))?;
+                match kind {
+                    Some('j') => {
+                        expect(1)?;
+                        Jmp { addr: number(operands[0])?, condition: Some(condition) }
+                    }
+                    Some('c') => {
+                        expect(1)?;
+                        Call { addr: number(operands[0])?, condition: Some(condition) }
+                    }
+                    Some('r') => {
+                        expect(0)?;
+                        Ret { condition: Some(condition) }
+                    }
+                    _ => return Err(ParseError::UnknownMnemonic(mnemonic.to_string())),
+                }
+            }
+        };
+
+        Ok(instr)
+    }
+}
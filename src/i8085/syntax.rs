@@ -0,0 +1,253 @@
+//! Writing instructions out in each [`Syntax`]: the Intel family, which differ only in how
+//! they name register pairs and in case, and Zilog's. The Intel family write
+//! [`Instruction::operands`] as they are.
+
+use crate::symbols::Symbols;
+use crate::syntax::Syntax;
+use super::{Instruction, Operand, Register, RegisterPair};
+
+/// `operand` by its name in `symbols`, or as a number.
+fn named(operand: Operand, symbols: &Symbols) -> String {
+    match operand.name(symbols) {
+        Some(name) => name,
+        None => format!("{:#x}", operand.value().unwrap_or_default()),
+    }
+}
+
+fn bare(mnemonic: &str) -> (String, Vec<String>) {
+    (mnemonic.to_string(), Vec::new())
+}
+
+fn with(mnemonic: &str, operands: &[String]) -> (String, Vec<String>) {
+    (mnemonic.to_string(), operands.to_vec())
+}
+
+impl Instruction {
+    /// Renders in `syntax`, naming addresses and ports from `symbols`. LXI's operand is only
+    /// named after data symbols, since it's as likely to be a count as a pointer.
+    pub fn asm_in(&self, syntax: Syntax, symbols: &Symbols) -> String {
+        let (mnemonic, operands) = match syntax {
+            Syntax::Zilog => self.zilog(symbols),
+            _ => self.intel(syntax, symbols),
+        };
+        let separator = match syntax {
+            Syntax::Raw | Syntax::Intel => ", ",
+            Syntax::Zilog | Syntax::Tdl => ",",
+        };
+        if operands.is_empty() {
+            mnemonic
+        } else {
+            format!("{} {}", mnemonic, operands.join(separator))
+        }
+    }
+
+    fn intel(&self, syntax: Syntax, symbols: &Symbols) -> (String, Vec<String>) {
+        use Instruction::*;
+        let case = |text: String| if syntax == Syntax::Tdl { text.to_uppercase() } else { text };
+        let mnemonic = match self {
+            // AS calls the K flag X5
+            Jnk { .. } if syntax != Syntax::Raw => "jnx5".to_string(),
+            Jk { .. } if syntax != Syntax::Raw => "jx5".to_string(),
+            Jmp { condition: Some(cond), .. } => format!("j{}", cond),
+            Call { condition: Some(cond), .. } => format!("c{}", cond),
+            Ret { condition: Some(cond) } => format!("r{}", cond),
+            _ => self.intel_mnemonic().to_string(),
+        };
+        let operands = self.operands()
+            .map(|(_, operand)| match operand {
+                Operand::Register(reg) => case(reg.to_string()),
+                Operand::RegisterPair(pair) if syntax == Syntax::Raw => pair.to_string(),
+                Operand::RegisterPair(pair) => case(pair.intel_name().to_string()),
+                _ => named(operand, symbols),
+            })
+            .collect();
+        (case(mnemonic), operands)
+    }
+
+    /// Intel's mnemonic, for unconditional forms.
+    fn intel_mnemonic(&self) -> &'static str {
+        use Instruction::*;
+        match self {
+            Nop => "nop",
+            Hlt => "hlt",
+            Xthl => "xthl",
+            Xchg => "xchg",
+            Pchl => "pchl",
+            Sphl => "sphl",
+            Rst { .. } => "rst",
+            Rstv => "rstv",
+            Dsub => "dsub",
+            Arhl => "arhl",
+            Rdel => "rdel",
+            Shlx => "shlx",
+            Lhlx => "lhlx",
+            Rlc => "rlc",
+            Ral => "ral",
+            Rrc => "rrc",
+            Rar => "rar",
+            Ei => "ei",
+            Di => "di",
+            Daa => "daa",
+            Stc => "stc",
+            Cma => "cma",
+            Cmc => "cmc",
+            Rim => "rim",
+            Sim => "sim",
+            Ldhi { .. } => "ldhi",
+            Ldsi { .. } => "ldsi",
+            Jnk { .. } => "jnk",
+            Jk { .. } => "jk",
+            Mov { .. } => "mov",
+            Adi { .. } => "adi",
+            Aci { .. } => "aci",
+            Sui { .. } => "sui",
+            Sbi { .. } => "sbi",
+            Ani { .. } => "ani",
+            Ori { .. } => "ori",
+            Xri { .. } => "xri",
+            Cpi { .. } => "cpi",
+            Add { .. } => "add",
+            Adc { .. } => "adc",
+            Sub { .. } => "sub",
+            Sbb { .. } => "sbb",
+            Ana { .. } => "ana",
+            Ora { .. } => "ora",
+            Xra { .. } => "xra",
+            Cmp { .. } => "cmp",
+            Pop { .. } => "pop",
+            Push { .. } => "push",
+            Stax { .. } => "stax",
+            Ldax { .. } => "ldax",
+            Inx { .. } => "inx",
+            Dcx { .. } => "dcx",
+            Inr { .. } => "inr",
+            Dcr { .. } => "dcr",
+            Lxi { .. } => "lxi",
+            Mvi { .. } => "mvi",
+            Dad { .. } => "dad",
+            In { .. } => "in",
+            Out { .. } => "out",
+            Lda { .. } => "lda",
+            Sta { .. } => "sta",
+            Lhld { .. } => "lhld",
+            Shld { .. } => "shld",
+            Jmp { .. } => "jmp",
+            Call { .. } => "call",
+            Ret { .. } => "ret",
+        }
+    }
+
+    fn zilog(&self, symbols: &Symbols) -> (String, Vec<String>) {
+        use Instruction::*;
+        let address = |addr: u16| named(Operand::Code(addr), symbols);
+        let reg = |reg: &Register| match reg {
+            Register::Mem => "(hl)".to_string(),
+            _ => reg.to_string(),
+        };
+        let pair = |pair: &RegisterPair| match pair {
+            RegisterPair::PSW => "af".to_string(),
+            _ => pair.to_string(),
+        };
+        let imm = |value: u8| format!("{:#x}", value);
+        let a = || "a".to_string();
+        let hl = || "hl".to_string();
+        let de = || "de".to_string();
+        let indirect = |text: String| format!("({})", text);
+        match self {
+            Nop => bare("nop"),
+            Hlt => bare("halt"),
+            Xthl => with("ex", &["(sp)".to_string(), hl()]),
+            Xchg => with("ex", &[de(), hl()]),
+            Pchl => with("jp", &[indirect(hl())]),
+            Sphl => with("ld", &["sp".to_string(), hl()]),
+
+            Rst { index } => with("rst", &[imm(*index * 8)]),
+            // the 8085's own, and undocumented, so there's no Zilog name for these
+            Rstv => bare("rstv"),
+            Rim => bare("rim"),
+            Sim => bare("sim"),
+
+            Dsub => with("sub", &[hl(), "bc".to_string()]),
+            Arhl => with("sra", &[hl()]),
+            Rdel => with("rl", &[de()]),
+            Shlx => with("ld", &[indirect(de()), hl()]),
+            Lhlx => with("ld", &[hl(), indirect(de())]),
+
+            Rlc => bare("rlca"),
+            Ral => bare("rla"),
+            Rrc => bare("rrca"),
+            Rar => bare("rra"),
+            Ei => bare("ei"),
+            Di => bare("di"),
+
+            Daa => bare("daa"),
+            Stc => bare("scf"),
+            Cma => bare("cpl"),
+            Cmc => bare("ccf"),
+
+            Ldhi { imm: value } => with("ld", &[de(), format!("hl+{}", imm(*value))]),
+            Ldsi { imm: value } => with("ld", &[de(), format!("sp+{}", imm(*value))]),
+
+            Jnk { addr } => with("jp", &["nx5".to_string(), address(*addr)]),
+            Jk { addr } => with("jp", &["x5".to_string(), address(*addr)]),
+
+            Mov { src, dest } => with("ld", &[reg(dest), reg(src)]),
+
+            Adi { value } => with("add", &[a(), imm(*value)]),
+            Aci { value } => with("adc", &[a(), imm(*value)]),
+            Sui { value } => with("sub", &[imm(*value)]),
+            Sbi { value } => with("sbc", &[a(), imm(*value)]),
+            Ani { value } => with("and", &[imm(*value)]),
+            Ori { value } => with("or", &[imm(*value)]),
+            Xri { value } => with("xor", &[imm(*value)]),
+            Cpi { value } => with("cp", &[imm(*value)]),
+
+            Add { reg: r } => with("add", &[a(), reg(r)]),
+            Adc { reg: r } => with("adc", &[a(), reg(r)]),
+            Sub { reg: r } => with("sub", &[reg(r)]),
+            Sbb { reg: r } => with("sbc", &[a(), reg(r)]),
+            Ana { reg: r } => with("and", &[reg(r)]),
+            Ora { reg: r } => with("or", &[reg(r)]),
+            Xra { reg: r } => with("xor", &[reg(r)]),
+            Cmp { reg: r } => with("cp", &[reg(r)]),
+
+            Pop { reg_pair } => with("pop", &[pair(reg_pair)]),
+            Push { reg_pair } => with("push", &[pair(reg_pair)]),
+
+            Stax { ptr } => with("ld", &[indirect(pair(ptr)), a()]),
+            Ldax { ptr } => with("ld", &[a(), indirect(pair(ptr))]),
+
+            Inx { reg_pair } => with("inc", &[pair(reg_pair)]),
+            Dcx { reg_pair } => with("dec", &[pair(reg_pair)]),
+
+            Inr { reg: r } => with("inc", &[reg(r)]),
+            Dcr { reg: r } => with("dec", &[reg(r)]),
+
+            Lxi { reg: r, value } => with("ld", &[pair(r), named(Operand::Imm16(*value), symbols)]),
+            Mvi { reg: r, value } => with("ld", &[reg(r), imm(*value)]),
+
+            Dad { reg_pair } => with("add", &[hl(), pair(reg_pair)]),
+
+            In { port } => with("in", &[a(), indirect(named(Operand::Port(*port), symbols))]),
+            Out { port } => with("out", &[indirect(named(Operand::Port(*port), symbols)), a()]),
+
+            Lda { addr } => with("ld", &[a(), indirect(address(*addr))]),
+            Sta { addr } => with("ld", &[indirect(address(*addr)), a()]),
+            Lhld { addr } => with("ld", &[hl(), indirect(address(*addr))]),
+            Shld { addr } => with("ld", &[indirect(address(*addr)), hl()]),
+
+            Jmp { addr, condition } => match condition {
+                None => with("jp", &[address(*addr)]),
+                Some(cond) => with("jp", &[cond.to_string(), address(*addr)]),
+            },
+            Call { addr, condition } => match condition {
+                None => with("call", &[address(*addr)]),
+                Some(cond) => with("call", &[cond.to_string(), address(*addr)]),
+            },
+            Ret { condition } => match condition {
+                None => bare("ret"),
+                Some(cond) => with("ret", &[cond.to_string()]),
+            },
+        }
+    }
+}
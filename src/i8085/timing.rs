@@ -0,0 +1,117 @@
+use std::fmt;
+
+use super::{Cpu, Instruction, Register};
+
+/// How many T-states an instruction takes to execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cycles {
+    Fixed(u32),
+    /// Conditional jumps, calls and returns take longer when the condition holds.
+    Conditional { not_taken: u32, taken: u32 },
+}
+
+impl Cycles {
+    pub fn not_taken(&self) -> u32 {
+        match *self {
+            Cycles::Fixed(n) => n,
+            Cycles::Conditional { not_taken, .. } => not_taken,
+        }
+    }
+
+    pub fn taken(&self) -> u32 {
+        match *self {
+            Cycles::Fixed(n) => n,
+            Cycles::Conditional { taken, .. } => taken,
+        }
+    }
+}
+
+impl fmt::Display for Cycles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cycles::Fixed(n) => write!(f, "{}", n),
+            Cycles::Conditional { not_taken, taken } => write!(f, "{}/{}", not_taken, taken),
+        }
+    }
+}
+
+impl Instruction {
+    /// T-states taken on `cpu`. Instructions the 8080 doesn't have are timed as on the 8085.
+    pub fn cycles(&self, cpu: Cpu) -> Cycles {
+        use Instruction::*;
+        use Cycles::*;
+        let i8080 = cpu == Cpu::I8080;
+        let pick = |on_8080, on_8085| if i8080 { on_8080 } else { on_8085 };
+        match *self {
+            Nop | Ei | Di | Xchg | Daa | Stc | Cma | Cmc | Rim | Sim => Fixed(4),
+            Rlc | Ral | Rrc | Rar => Fixed(4),
+            Hlt => Fixed(pick(7, 5)),
+
+            Mov { src: Register::Mem, .. } | Mov { dest: Register::Mem, .. } => Fixed(7),
+            Mov { .. } => Fixed(pick(5, 4)),
+            Mvi { reg: Register::Mem, .. } => Fixed(10),
+            Mvi { .. } => Fixed(7),
+            Lxi { .. } => Fixed(10),
+
+            Stax { .. } | Ldax { .. } => Fixed(7),
+            Lda { .. } | Sta { .. } => Fixed(13),
+            Lhld { .. } | Shld { .. } => Fixed(16),
+
+            Inx { .. } | Dcx { .. } => Fixed(pick(5, 6)),
+            Inr { reg: Register::Mem } | Dcr { reg: Register::Mem } => Fixed(10),
+            Inr { .. } | Dcr { .. } => Fixed(pick(5, 4)),
+            Dad { .. } => Fixed(10),
+
+            Add { reg } | Adc { reg } | Sub { reg } | Sbb { reg }
+            | Ana { reg } | Ora { reg } | Xra { reg } | Cmp { reg } => {
+                Fixed(if reg == Register::Mem { 7 } else { 4 })
+            }
+            Adi { .. } | Aci { .. } | Sui { .. } | Sbi { .. }
+            | Ani { .. } | Ori { .. } | Xri { .. } | Cpi { .. } => Fixed(7),
+
+            In { .. } | Out { .. } => Fixed(10),
+
+            Jmp { condition: None, .. } => Fixed(10),
+            Jmp { condition: Some(_), .. } if i8080 => Fixed(10),
+            Jmp { condition: Some(_), .. } => Conditional { not_taken: 7, taken: 10 },
+            Call { condition: None, .. } => Fixed(pick(17, 18)),
+            Call { condition: Some(_), .. } if i8080 => Conditional { not_taken: 11, taken: 17 },
+            Call { condition: Some(_), .. } => Conditional { not_taken: 9, taken: 18 },
+            Ret { condition: None } => Fixed(10),
+            Ret { condition: Some(_) } if i8080 => Conditional { not_taken: 5, taken: 11 },
+            Ret { condition: Some(_) } => Conditional { not_taken: 6, taken: 12 },
+            Rst { .. } => Fixed(pick(11, 12)),
+
+            Push { .. } => Fixed(pick(11, 12)),
+            Pop { .. } => Fixed(10),
+            Xthl => Fixed(pick(18, 16)),
+            Pchl | Sphl => Fixed(pick(5, 6)),
+
+            Dsub | Rdel | Ldhi { .. } | Ldsi { .. } | Shlx | Lhlx => Fixed(10),
+            Arhl => Fixed(7),
+            Jk { .. } | Jnk { .. } => Conditional { not_taken: 7, taken: 10 },
+            Rstv => Conditional { not_taken: 6, taken: 12 },
+        }
+    }
+}
+
+/// Total T-states for running straight through `instrs`, with every conditional falling
+/// through.
+pub fn straight_line<'a, I>(instrs: I, cpu: Cpu) -> u32 where I: IntoIterator<Item = &'a Instruction> {
+    instrs.into_iter().map(|instr| instr.cycles(cpu).not_taken()).sum()
+}
+
+/// Total T-states for a counted loop whose `body` ends in a conditional branch back to its
+/// start, run for `iterations` passes: the branch is taken every time but the last.
+pub fn counted_loop(body: &[Instruction], iterations: u32, cpu: Cpu) -> u32 {
+    let (back_edge, straight) = match body.split_last() {
+        Some(split) => split,
+        None => return 0,
+    };
+    if iterations == 0 {
+        return 0;
+    }
+
+    let back_edge = back_edge.cycles(cpu);
+    iterations * straight_line(straight, cpu) + (iterations - 1) * back_edge.taken() + back_edge.not_taken()
+}
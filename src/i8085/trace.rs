@@ -1,12 +1,453 @@
-use super::memory::BankFlags;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use super::memory::{BankMap, Selector};
+use super::{Cpu, DecodeError, Flow, Instruction, Register, RegisterPair, Regs};
+use crate::memory::MemoryImage;
+use crate::printer::{Address, BankId, BankedAddress, Entry};
+use crate::xref::{Xref, XrefKind, Xrefs};
+
+/// Addresses the 8085 can start executing at without being jumped to: the reset vector
+/// (which doubles as RST 0), the remaining RST vectors, and the TRAP and RST 5.5/6.5/7.5
+/// interrupt vectors. Reset comes first, since it's the one certain to be code.
+pub const ENTRY_POINTS: [u16; 12] = [
+    0x00, 0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38,
+    0x24, 0x2c, 0x34, 0x3c,
+];
+
+/// Longest run of data bytes put in a single listing entry.
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteKind {
+    /// Never reached by the tracer.
+    Data,
+    /// First byte of an instruction.
+    Opcode,
+    /// Operand byte of the instruction before it.
+    Operand,
+}
+
+enum Memory<'a> {
+    Flat(&'a MemoryImage),
+    Banked { map: &'a BankMap, file: &'a [u8] },
+}
 
 pub struct Tracer<'a> {
-    mem: &'a [u8],
+    mem: Memory<'a>,
+    cpu: Cpu,
+    selector: Option<u8>,
 }
 
+/// What's known of the bank selector's value: the bits set in `known`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct SelectorState {
+    value: u8,
+    known: u8,
+}
+
+#[derive(Clone)]
 pub struct ProcessorState {
     pc: u16,
     // flag dirtiness?
-    // bank switching?
-    bankflags: BankFlags,
+    a: Option<u8>,
+    /// Pointers, for following LDAX and STAX.
+    bc: Option<u16>,
+    de: Option<u16>,
+    selector: SelectorState,
+}
+
+/// The code found in one bank, as seen through one window; or everything, if the image
+/// isn't banked.
+struct View<'a> {
+    bank: Option<BankId>,
+    mem: Cow<'a, MemoryImage>,
+    /// Code bytes; anything else readable is data.
+    map: BTreeMap<usize, ByteKind>,
+    instructions: BTreeMap<usize, (usize, Instruction)>,
+}
+
+/// The result of tracing an image: which bytes are code, and the instructions found.
+pub struct Trace<'a> {
+    entries: Vec<u16>,
+    views: Vec<View<'a>>,
+    errors: Vec<DecodeError>,
+    xrefs: Xrefs,
+}
+
+/// A listing of one bank's part of a trace.
+pub struct Section<'a> {
+    /// `None` unless the image is banked
+    pub bank: Option<BankId>,
+    pub mem: Cow<'a, MemoryImage>,
+    pub listing: Vec<(BankedAddress, Entry<Instruction>)>,
+}
+
+impl<'a> Tracer<'a> {
+    pub fn new(mem: &'a MemoryImage) -> Tracer<'a> {
+        Tracer { mem: Memory::Flat(mem), cpu: Cpu::default(), selector: None }
+    }
+
+    /// Trace through the banks of `map`, with ROM contents from `file`. The selector is
+    /// followed through `mvi a` and writes of A to it; wherever it isn't known, every bank
+    /// is tried.
+    pub fn banked(map: &'a BankMap, file: &'a [u8]) -> Tracer<'a> {
+        Tracer { mem: Memory::Banked { map, file }, cpu: Cpu::default(), selector: None }
+    }
+
+    pub fn with_cpu(mut self, cpu: Cpu) -> Tracer<'a> {
+        self.cpu = cpu;
+        self
+    }
+
+    /// Start with the bank selector holding `value`, rather than unknown.
+    pub fn with_selector(mut self, value: u8) -> Tracer<'a> {
+        self.selector = Some(value);
+        self
+    }
+
+    /// Follows control flow from each of `entries` in turn, decoding everything reachable.
+    /// Where two entries disagree on which bytes are instructions, the earlier one wins.
+    pub fn trace(&self, entries: &[u16]) -> Trace<'a> {
+        let mut trace = Trace { entries: entries.to_vec(), views: Vec::new(), errors: Vec::new(), xrefs: Xrefs::default() };
+        if let Memory::Flat(mem) = self.mem {
+            trace.views.push(View::new(None, Cow::Borrowed(mem)));
+        }
+        // views of banked memory, by bank and window
+        let mut views: HashMap<(BankId, u16), usize> = HashMap::new();
+        let mut visited: HashMap<_, ProcessorState> = HashMap::new();
+
+        let selector = match self.selector {
+            Some(value) => SelectorState { value, known: 0xff },
+            None => SelectorState::default(),
+        };
+        // a stack, so the first entry goes on last
+        let mut pending: Vec<ProcessorState> = entries.iter().rev()
+            .map(|&pc| ProcessorState { pc, a: None, bc: None, de: None, selector })
+            .collect();
+
+        while let Some(state) = pending.pop() {
+            let view = match self.mem {
+                Memory::Flat(_) => 0,
+                Memory::Banked { map, file } => {
+                    let window = match map.window(state.pc) {
+                        Some(window) => window,
+                        None => continue,
+                    };
+                    let mask = window.field.mask();
+                    if state.selector.known & mask != mask {
+                        for value in 0..1u16 << window.field.width {
+                            let mut forked = state.clone();
+                            forked.selector.value = forked.selector.value & !mask | (value << window.field.shift) as u8;
+                            forked.selector.known |= mask;
+                            pending.push(forked);
+                        }
+                        continue;
+                    }
+                    let bank = match map.selected(window, state.selector.value) {
+                        Some(bank) if bank.file_offset.is_some() => bank,
+                        _ => continue,
+                    };
+                    let id = map.id(&bank.name).expect("selected bank is in the map");
+                    *views.entry((id, window.base)).or_insert_with(|| {
+                        let mut mem = MemoryImage::default();
+                        let mapping = map.mapping(file, state.selector.value);
+                        if let Some(region) = mapping.region(window.base as usize) {
+                            mem.map(region.clone()).ok();
+                        }
+                        trace.views.push(View::new(Some(id), Cow::Owned(mem)));
+                        trace.views.len() - 1
+                    })
+                }
+            };
+            // the same code may need following again with another bank selected; and if
+            // it's reached again with other register values, with whatever the paths agree on
+            let mut state = state;
+            match visited.get_mut(&(view, state.pc, state.selector)) {
+                Some(seen) => {
+                    if !seen.merge(&state) {
+                        continue;
+                    }
+                    state = seen.clone();
+                }
+                None => {
+                    visited.insert((view, state.pc, state.selector), state.clone());
+                }
+            }
+
+            let addr = state.pc as usize;
+            let view = &mut trace.views[view];
+            let (len, instr) = match view.instructions.get(&addr) {
+                Some((len, instr)) => (*len, instr.clone()),
+                None => {
+                    // unreadable, or the middle of another instruction, which we don't try
+                    // to untangle.
+                    if view.mem.read(addr).is_none() || view.map.contains_key(&addr) {
+                        continue;
+                    }
+
+                    let (len, instr) = match Instruction::decode_at(&view.mem, addr, self.cpu) {
+                        Ok(decoded) => decoded,
+                        Err(e) => {
+                            // we'll be back here for each selector value
+                            if !trace.errors.contains(&e) {
+                                trace.errors.push(e);
+                            }
+                            continue;
+                        }
+                    };
+
+                    if (addr..addr + len).any(|a| view.map.contains_key(&a)) {
+                        continue;
+                    }
+
+                    view.map.insert(addr, ByteKind::Opcode);
+                    for a in addr + 1..addr + len {
+                        view.map.insert(a, ByteKind::Operand);
+                    }
+                    view.instructions.insert(addr, (len, instr.clone()));
+                    (len, instr)
+                }
+            };
+
+            let from = BankedAddress::new(view.bank, addr);
+            for (to, kind) in references(&state, &instr) {
+                if kind.is_io() {
+                    trace.xrefs.insert(Xref { to: to.into(), from, kind });
+                    continue;
+                }
+                let banks = self.banks_at(to, state.selector);
+                trace.xrefs.extend(banks.into_iter().map(|bank| Xref { to: BankedAddress::new(bank, to), from, kind }));
+            }
+
+            let after = self.step(&state, &instr);
+            let flow = instr.flow();
+            if flow.falls_through() {
+                let next = state.pc.wrapping_add(len as u16);
+                match flow {
+                    // the callee may have changed anything by the time it returns
+                    Flow::Call { .. } | Flow::Trap { .. } => pending.push(ProcessorState::unknown(next)),
+                    _ => pending.push(ProcessorState { pc: next, ..after.clone() }),
+                }
+            }
+            if let Some(target) = flow.target() {
+                pending.push(ProcessorState { pc: target, ..after });
+            }
+        }
+
+        trace
+    }
+
+    /// The banks `addr` may be in, as far as the selector is known: each bank its window
+    /// could show, or just `None` if the image isn't banked or nothing is mapped there.
+    fn banks_at(&self, addr: Address, selector: SelectorState) -> Vec<Option<BankId>> {
+        let map = match self.mem {
+            Memory::Banked { map, .. } => map,
+            Memory::Flat(_) => return vec![None],
+        };
+        let window = match map.window(addr as u16) {
+            Some(window) => window,
+            None => return vec![None],
+        };
+        let known = selector.known & window.field.mask();
+        let mut banks = Vec::new();
+        for value in 0..1u16 << window.field.width {
+            let value = (value << window.field.shift) as u8;
+            if (value ^ selector.value) & known != 0 {
+                continue;
+            }
+            let id = map.selected(window, value).and_then(|bank| map.id(&bank.name));
+            if id.is_some() && !banks.contains(&id) {
+                banks.push(id);
+            }
+        }
+        if banks.is_empty() {
+            banks.push(None);
+        }
+        banks
+    }
+
+    /// What's known of A, the pointers and the bank selector after `instr`.
+    fn step(&self, state: &ProcessorState, instr: &Instruction) -> ProcessorState {
+        let selector = match self.mem {
+            Memory::Banked { map, .. } => Some(map.selector),
+            Memory::Flat(_) => None,
+        };
+
+        let mut after = state.clone();
+        if selector.is_some() && a_written(instr) == selector {
+            after.selector = match state.a {
+                Some(value) => SelectorState { value, known: 0xff },
+                None => SelectorState::default(),
+            };
+        }
+        match instr {
+            Instruction::Mvi { reg: Register::A, value } => after.a = Some(*value),
+            _ if instr.defs().contains(Regs::A) => after.a = None,
+            _ => {}
+        }
+        let pointer = |value: Option<u16>, reg_pair: RegisterPair| match *instr {
+            Instruction::Lxi { reg, value } if reg == reg_pair => Some(value),
+            Instruction::Inx { reg_pair: reg } if reg == reg_pair => value.map(|v| v.wrapping_add(1)),
+            Instruction::Dcx { reg_pair: reg } if reg == reg_pair => value.map(|v| v.wrapping_sub(1)),
+            _ if instr.defs().intersects(reg_pair.into()) => None,
+            _ => value,
+        };
+        after.bc = pointer(state.bc, RegisterPair::BC);
+        after.de = pointer(state.de, RegisterPair::DE);
+        after
+    }
+}
+
+/// What `instr` refers to, as run in `state`: its jump or call target, memory it reads or
+/// writes where the address is known, the address it loads with LXI, or its port.
+fn references(state: &ProcessorState, instr: &Instruction) -> Vec<(Address, XrefKind)> {
+    use Instruction::*;
+    let mut refs = Vec::new();
+    match instr.flow() {
+        Flow::Branch { target } | Flow::ConditionalBranch { target } => refs.push((target, XrefKind::Jump)),
+        Flow::Call { target } | Flow::Trap { vector: target } => refs.push((target, XrefKind::Call)),
+        _ => {}
+    }
+    let via = |ptr: RegisterPair| match ptr {
+        RegisterPair::BC => state.bc,
+        RegisterPair::DE => state.de,
+        _ => None,
+    };
+    match *instr {
+        Lda { addr } | Lhld { addr } => refs.push((addr, XrefKind::Read)),
+        Sta { addr } | Shld { addr } => refs.push((addr, XrefKind::Write)),
+        Ldax { ptr } => refs.extend(via(ptr).map(|addr| (addr, XrefKind::Read))),
+        Stax { ptr } => refs.extend(via(ptr).map(|addr| (addr, XrefKind::Write))),
+        Lhlx => refs.extend(state.de.map(|addr| (addr, XrefKind::Read))),
+        Shlx => refs.extend(state.de.map(|addr| (addr, XrefKind::Write))),
+        Lxi { value, .. } => refs.push((value, XrefKind::Pointer)),
+        In { port } => refs.push((port as u16, XrefKind::In)),
+        Out { port } => refs.push((port as u16, XrefKind::Out)),
+        _ => {}
+    }
+    refs.into_iter().map(|(to, kind)| (to as Address, kind)).collect()
+}
+
+/// Where `instr` stores A, if it's somewhere a selector could be.
+fn a_written(instr: &Instruction) -> Option<Selector> {
+    match *instr {
+        Instruction::Out { port } => Some(Selector::Port(port)),
+        Instruction::Sta { addr } => Some(Selector::Address(addr)),
+        _ => None,
+    }
+}
+
+impl ProcessorState {
+    /// At `pc`, knowing nothing of the registers or the selector.
+    fn unknown(pc: u16) -> ProcessorState {
+        ProcessorState { pc, a: None, bc: None, de: None, selector: SelectorState::default() }
+    }
+
+    /// Forgets whatever `other` disagrees on; returns whether anything was forgotten.
+    fn merge(&mut self, other: &ProcessorState) -> bool {
+        let before = (self.a, self.bc, self.de);
+        if self.a != other.a {
+            self.a = None;
+        }
+        if self.bc != other.bc {
+            self.bc = None;
+        }
+        if self.de != other.de {
+            self.de = None;
+        }
+        (self.a, self.bc, self.de) != before
+    }
+}
+
+impl<'a> View<'a> {
+    fn new(bank: Option<BankId>, mem: Cow<'a, MemoryImage>) -> View<'a> {
+        View { bank, mem, map: BTreeMap::new(), instructions: BTreeMap::new() }
+    }
+
+    /// Lays out every readable region as a listing, with untraced bytes as data.
+    fn into_section(self) -> Section<'a> {
+        let View { bank, mem, map, instructions } = self;
+        let mut listing = Vec::new();
+        let mut instructions = instructions.into_iter().peekable();
+        // an instruction may run on into the next region
+        let mut addr = 0;
+        for region in mem.regions().iter().filter(|r| r.readable) {
+            let end = region.base + region.data.len();
+            addr = addr.max(region.base);
+            while addr < end {
+                if let Some((len, instr)) = instructions.next_if(|(a, _)| *a == addr).map(|(_, i)| i) {
+                    listing.push((BankedAddress::new(bank, addr), Entry::Code(instr)));
+                    addr += len;
+                    continue;
+                }
+
+                let mut run = addr;
+                while run < end && run - addr < DATA_PER_LINE && !map.contains_key(&run) {
+                    run += 1;
+                }
+                // only reachable if something jumped into the middle of an instruction
+                let run = run.max(addr + 1);
+                let bytes = region.data[addr - region.base..run - region.base].to_vec();
+                listing.push((BankedAddress::new(bank, addr), Entry::Data(bytes)));
+                addr = run;
+            }
+        }
+
+        Section { bank, mem, listing }
+    }
+}
+
+impl<'a> Trace<'a> {
+    /// What the byte at `at` is, or `None` if it isn't readable or its bank wasn't traced
+    /// into.
+    pub fn kind<A>(&self, at: A) -> Option<ByteKind> where A: Into<BankedAddress> {
+        let at = at.into();
+        self.views.iter().filter(|view| view.bank == at.bank).find_map(|view| match view.map.get(&at.addr) {
+            Some(&kind) => Some(kind),
+            None => view.mem.read(at.addr).map(|_| ByteKind::Data),
+        })
+    }
+
+    /// Where tracing started from, in each bank it found code there.
+    pub fn entries(&self) -> Vec<BankedAddress> {
+        let mut entries: Vec<BankedAddress> = self.views.iter()
+            .flat_map(|view| self.entries.iter()
+                .map(move |&addr| BankedAddress::new(view.bank, addr as Address))
+                .filter(move |at| view.instructions.contains_key(&at.addr)))
+            .collect();
+        entries.sort();
+        entries.dedup();
+        entries
+    }
+
+    /// Every traced instruction as `(address, length, instruction)`, in address order
+    /// within each bank.
+    pub fn instructions(&self) -> impl Iterator<Item = (BankedAddress, usize, &Instruction)> {
+        self.views.iter().flat_map(|view| view.instructions.iter()
+            .map(move |(&addr, (len, instr))| (BankedAddress::new(view.bank, addr), *len, instr)))
+    }
+
+    /// What each traced instruction refers to. Pointers in BC and DE are followed from the
+    /// LXI which set them, along each path traced, but not past a call returning.
+    pub fn xrefs(&self) -> &Xrefs {
+        &self.xrefs
+    }
+
+    /// Decode failures hit while following control flow.
+    pub fn errors(&self) -> &[DecodeError] {
+        &self.errors
+    }
+
+    /// Lays out the whole image as a listing, with untraced bytes as data, bank by bank.
+    pub fn into_listing(self) -> Vec<(BankedAddress, Entry<Instruction>)> {
+        self.into_sections().into_iter().flat_map(|section| section.listing).collect()
+    }
+
+    /// A listing for each bank traced into, in address order.
+    pub fn into_sections(self) -> Vec<Section<'a>> {
+        let mut sections: Vec<Section<'a>> = self.views.into_iter().map(View::into_section).collect();
+        sections.sort_by_key(|section| section.listing.first().map(|(at, _)| (at.addr, at.bank)));
+        sections
+    }
 }
@@ -0,0 +1,254 @@
+//! Listings as JSON, for scripts and diffing. The schema is versioned: [`VERSION`] goes up
+//! whenever a field is removed or changes meaning, while new fields may turn up without it.
+//! Every field is always present, `null` where it doesn't apply. For streaming,
+//! [`Printer::print_jsonl`](crate::Printer::print_jsonl) writes a [`Header`] and then the
+//! lines, one object to each line of text.
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "cpu": "8085undoc",
+//!   "segments": [
+//!     {
+//!       "bank": null, "bank_name": null, "start": 0, "end": 6,
+//!       "lines": [
+//!         {
+//!           "address": 0, "bank": null, "bytes": [205, 4, 0], "kind": "code",
+//!           "mnemonic": "call", "text": "call sub_0004",
+//!           "operands": [
+//!             {"type": "code", "offset": 1, "register": null, "value": 4, "name": "sub_0004"}
+//!           ],
+//!           "flow": "call", "targets": [4], "labels": [], "comments": []
+//!         }
+//!       ]
+//!     }
+//!   ],
+//!   "symbols": [{"address": 4, "bank": null, "name": "sub_0004", "kind": "code"}],
+//!   "ports": [{"port": 16, "name": "uart"}],
+//!   "xrefs": [{"to": 4, "to_bank": null, "from": 0, "bank": null, "kind": "call"}]
+//! }
+//! ```
+//!
+//! Addresses, bytes and values are numbers. Operand types are `register`, `register_pair`,
+//! `imm8`, `imm16`, `code`, `data`, `port` and `vector`; flows are `fallthrough`, `branch`,
+//! `conditional_branch`, `call`, `return`, `conditional_return`, `indirect`, `halt` and
+//! `trap`. Data lines have no operands and a `null` flow. An xref's `bank` is that of the
+//! instruction it's from and `to_bank` that of what it refers to; for `in` and `out`, `to`
+//! is a port.
+
+use serde::Serialize;
+
+use crate::flow::Flow;
+use crate::i8085;
+use crate::printer::{Address, BankId};
+use crate::symbols::{SymbolKind, Symbols};
+use crate::xref::Xrefs;
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperandKind {
+    Register,
+    RegisterPair,
+    Imm8,
+    Imm16,
+    Code,
+    Data,
+    Port,
+    Vector,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Operand {
+    #[serde(rename = "type")]
+    pub kind: OperandKind,
+    /// Into the line's bytes
+    pub offset: usize,
+    /// For registers and pairs
+    pub register: Option<String>,
+    pub value: Option<u16>,
+    pub name: Option<String>,
+}
+
+/// Operands which can be described as [`Operand`]s, for whatever [`crate::Print::code`]
+/// gives.
+pub trait ToOperand {
+    /// As JSON, `offset` into the encoding, named from `symbols`.
+    fn to_operand(&self, offset: usize, symbols: &Symbols) -> Operand;
+}
+
+impl ToOperand for i8085::Operand {
+    fn to_operand(&self, offset: usize, symbols: &Symbols) -> Operand {
+        use i8085::Operand as Op;
+        let (kind, register) = match self {
+            Op::Register(reg) => (OperandKind::Register, Some(reg.to_string())),
+            Op::RegisterPair(pair) => (OperandKind::RegisterPair, Some(pair.to_string())),
+            Op::Imm8(_) => (OperandKind::Imm8, None),
+            Op::Imm16(_) => (OperandKind::Imm16, None),
+            Op::Code(_) => (OperandKind::Code, None),
+            Op::Data(_) => (OperandKind::Data, None),
+            Op::Port(_) => (OperandKind::Port, None),
+            Op::Vector(_) => (OperandKind::Vector, None),
+        };
+        Operand { kind, offset, register, value: self.value(), name: self.name(symbols) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowKind {
+    Fallthrough,
+    Branch,
+    ConditionalBranch,
+    Call,
+    Return,
+    ConditionalReturn,
+    Indirect,
+    Halt,
+    Trap,
+}
+
+impl From<Flow> for FlowKind {
+    fn from(flow: Flow) -> FlowKind {
+        match flow {
+            Flow::Fallthrough => FlowKind::Fallthrough,
+            Flow::ConditionalBranch { .. } => FlowKind::ConditionalBranch,
+            Flow::Branch { .. } => FlowKind::Branch,
+            Flow::Call { .. } => FlowKind::Call,
+            Flow::Return => FlowKind::Return,
+            Flow::ConditionalReturn => FlowKind::ConditionalReturn,
+            Flow::Indirect => FlowKind::Indirect,
+            Flow::Halt => FlowKind::Halt,
+            Flow::Trap { .. } => FlowKind::Trap,
+        }
+    }
+}
+
+/// What a line of code does, from what [`crate::Print::code`] gives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Code {
+    pub operands: Vec<Operand>,
+    pub flow: FlowKind,
+    /// Where control may go, other than the next line
+    pub targets: Vec<Address>,
+}
+
+impl Code {
+    /// With `operands` named from `symbols`.
+    pub fn new<O>(operands: &[(usize, O)], flow: Flow, symbols: &Symbols) -> Code where O: ToOperand {
+        Code {
+            operands: operands.iter().map(|(offset, operand)| operand.to_operand(*offset, symbols)).collect(),
+            flow: flow.into(),
+            targets: flow.target().map(|target| target as Address).into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Code,
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Line {
+    pub address: Address,
+    pub bank: Option<BankId>,
+    pub bytes: Vec<u8>,
+    pub kind: LineKind,
+    pub mnemonic: String,
+    /// The whole line, as a listing shows it
+    pub text: String,
+    pub operands: Vec<Operand>,
+    pub flow: Option<FlowKind>,
+    pub targets: Vec<Address>,
+    pub labels: Vec<String>,
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Segment {
+    pub bank: Option<BankId>,
+    pub bank_name: Option<String>,
+    pub start: Address,
+    /// Just past the last line
+    pub end: Address,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    pub address: Address,
+    pub bank: Option<BankId>,
+    pub name: String,
+    /// `code` or `data`
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Port {
+    pub port: Address,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Xref {
+    pub to: Address,
+    pub to_bank: Option<BankId>,
+    pub from: Address,
+    pub bank: Option<BankId>,
+    /// As [`crate::XrefKind`] displays
+    pub kind: String,
+}
+
+/// The first record of a JSON lines stream, before the [`Line`]s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Header {
+    pub version: u32,
+    pub cpu: String,
+    /// Bank names, indexed by the lines' `bank`
+    pub banks: Vec<String>,
+}
+
+impl Header {
+    pub fn new(cpu: &str, banks: Vec<String>) -> Header {
+        Header { version: VERSION, cpu: cpu.to_string(), banks }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Document {
+    pub version: u32,
+    pub cpu: String,
+    pub segments: Vec<Segment>,
+    pub symbols: Vec<Symbol>,
+    pub ports: Vec<Port>,
+    pub xrefs: Vec<Xref>,
+}
+
+impl Document {
+    pub fn new(cpu: &str, segments: Vec<Segment>, symbols: &Symbols, xrefs: &Xrefs) -> Document {
+        Document {
+            version: VERSION,
+            cpu: cpu.to_string(),
+            segments,
+            symbols: symbols.iter()
+                .map(|(at, sym)| Symbol {
+                    address: at.addr,
+                    bank: at.bank,
+                    name: sym.name.clone(),
+                    kind: match sym.kind {
+                        SymbolKind::Code => "code".to_string(),
+                        SymbolKind::Data => "data".to_string(),
+                    },
+                })
+                .collect(),
+            ports: symbols.ports().map(|(port, name)| Port { port, name: name.to_string() }).collect(),
+            xrefs: xrefs.iter()
+                .map(|x| Xref { to: x.to.addr, to_bank: x.to.bank, from: x.from.addr, bank: x.from.bank, kind: x.kind.to_string() })
+                .collect(),
+        }
+    }
+}
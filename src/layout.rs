@@ -0,0 +1,165 @@
+//! Where each part of a listing line goes, and how its numbers are written.
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Address,
+    /// The bytes the line was decoded from
+    Bytes,
+    /// The line's label, if it has one. Without this column, labels get a line of their own.
+    Label,
+    /// T-states, for instructions
+    Cycles,
+    Mnemonic,
+    Operands,
+    Comment,
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Column, String> {
+        match s.to_lowercase().as_str() {
+            "address" => Ok(Column::Address),
+            "bytes" => Ok(Column::Bytes),
+            "label" => Ok(Column::Label),
+            "cycles" => Ok(Column::Cycles),
+            "mnemonic" => Ok(Column::Mnemonic),
+            "operands" => Ok(Column::Operands),
+            "comment" => Ok(Column::Comment),
+            _ => Err(format!("unknown column {:?} (expected address, bytes, label, cycles, mnemonic, operands or comment)", s)),
+        }
+    }
+}
+
+/// How hex numbers in operands and data are marked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HexStyle {
+    /// `0x1f`
+    #[default]
+    Prefix,
+    /// `$1f`
+    Dollar,
+    /// `1fh`, or `0ffh` where it would otherwise start with a letter
+    Suffix,
+}
+
+impl FromStr for HexStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<HexStyle, String> {
+        match s.to_lowercase().as_str() {
+            "0x" => Ok(HexStyle::Prefix),
+            "$" => Ok(HexStyle::Dollar),
+            "h" => Ok(HexStyle::Suffix),
+            _ => Err(format!("unknown hex style {:?} (expected 0x, $ or h)", s)),
+        }
+    }
+}
+
+impl HexStyle {
+    /// `digits`, already in hex, marked in this style.
+    pub fn mark(&self, digits: &str) -> String {
+        match self {
+            HexStyle::Prefix => format!("0x{}", digits),
+            HexStyle::Dollar => format!("${}", digits),
+            HexStyle::Suffix if digits.starts_with(|c: char| c.is_ascii_alphabetic()) => format!("0{}h", digits),
+            HexStyle::Suffix => format!("{}h", digits),
+        }
+    }
+
+    /// Rewrites the `0x` numbers in `text` in this style, leaving anything quoted alone.
+    pub fn restyle(&self, text: &str, uppercase: bool) -> String {
+        let mut out = String::new();
+        let mut quote = None;
+        let mut prev = ' ';
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c == '0' && chars.peek() == Some(&'x') && !(prev.is_ascii_alphanumeric() || prev == '_') => {
+                    chars.next();
+                    let mut digits = String::new();
+                    while let Some(&d) = chars.peek().filter(|d| d.is_ascii_hexdigit()) {
+                        digits.push(d);
+                        chars.next();
+                    }
+                    if uppercase {
+                        digits.make_ascii_uppercase();
+                    }
+                    out.push_str(&self.mark(&digits));
+                    prev = 'x';
+                    continue;
+                }
+                None => {}
+            }
+            out.push(c);
+            prev = c;
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// In the order they're printed
+    pub columns: Vec<Column>,
+    /// Most bytes shown in the bytes column; longer lines end with `+`
+    pub bytes: usize,
+    pub label_width: usize,
+    pub mnemonic_width: usize,
+    /// Operands are padded to this before a comment
+    pub operands_width: usize,
+    pub hex: HexStyle,
+    /// Upper-case hex digits
+    pub uppercase: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            columns: vec![Column::Address, Column::Mnemonic, Column::Operands, Column::Comment],
+            bytes: 3,
+            label_width: 16,
+            mnemonic_width: 0,
+            operands_width: 0,
+            hex: HexStyle::Prefix,
+            uppercase: false,
+        }
+    }
+}
+
+impl Layout {
+    /// Address, bytes, mnemonic, operands and comment: the usual listing.
+    pub fn listing() -> Layout {
+        let columns = vec![Column::Address, Column::Bytes, Column::Mnemonic, Column::Operands, Column::Comment];
+        Layout { columns, mnemonic_width: 6, ..Layout::default() }
+    }
+
+    /// Just the code, as you'd write it.
+    pub fn source() -> Layout {
+        Layout { columns: vec![Column::Label, Column::Mnemonic, Column::Operands, Column::Comment], ..Layout::default() }
+    }
+
+    pub fn has(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// `value` as hex of at least `width` digits, in this layout's case but without any
+    /// marking, as addresses and bytes are shown.
+    pub fn hex(&self, value: usize, width: usize) -> String {
+        if self.uppercase {
+            format!("{:0width$X}", value, width = width)
+        } else {
+            format!("{:0width$x}", value, width = width)
+        }
+    }
+
+    /// Width the bytes column is padded to, leaving room for the `+`.
+    pub fn bytes_width(&self) -> usize {
+        self.bytes * 3 + 1
+    }
+}
@@ -1,4 +1,20 @@
+pub mod data;
+pub mod flow;
 pub mod i8085;
+pub mod json;
+pub mod layout;
+pub mod loader;
+pub mod memory;
 pub mod printer;
+pub mod symbols;
+pub mod syntax;
+pub mod xref;
 
-pub use printer::{Printer, Print, AddressWidth};
+pub use printer::{Printer, Print, Entry, Address, AddressWidth, BankId, BankedAddress, LabelKind};
+pub use data::{DataFormat, Directive, Terminator};
+pub use flow::Flow;
+pub use layout::{Column, HexStyle, Layout};
+pub use memory::{MemoryImage, Region, RegionKind};
+pub use symbols::{Symbol, SymbolKind, Symbols};
+pub use syntax::Syntax;
+pub use xref::{Xref, XrefKind, Xrefs};
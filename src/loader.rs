@@ -0,0 +1,253 @@
+//! Loading firmware images: flat binaries, Intel HEX and Motorola S-records.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::printer::Address;
+
+/// A run of contiguous bytes starting at `addr`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub addr: Address,
+    pub data: Vec<u8>,
+}
+
+impl Segment {
+    pub fn end(&self) -> Address {
+        self.addr + self.data.len()
+    }
+}
+
+/// A sparse image: segments in address order, with gaps between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Image {
+    segments: Vec<Segment>,
+    /// Start address given by the file, if any.
+    pub entry: Option<Address>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadErrorKind {
+    Syntax(String),
+    Checksum { expected: u8, found: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    pub line: usize,
+    pub kind: LoadErrorKind,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LoadErrorKind::Syntax(message) => write!(f, "line {}: {}", self.line, message),
+            LoadErrorKind::Checksum { expected, found } =>
+                write!(f, "line {}: bad checksum {:#04x}, expected {:#04x}", self.line, found, expected),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+fn syntax(line: usize, message: &str) -> LoadError {
+    LoadError { line, kind: LoadErrorKind::Syntax(message.to_string()) }
+}
+
+/// Decodes a record's hex digits into bytes.
+fn hex_bytes(line: usize, s: &str) -> Result<Vec<u8>, LoadError> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return Err(syntax(line, "odd number of hex digits"));
+    }
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| syntax(line, "bad hex digit")))
+        .collect()
+}
+
+fn be_addr(bytes: &[u8]) -> Address {
+    bytes.iter().fold(0, |addr, &b| addr << 8 | b as Address)
+}
+
+impl Image {
+    /// A flat binary loaded at `base`.
+    pub fn raw(data: Vec<u8>, base: Address) -> Image {
+        let mut image = Image::default();
+        image.insert(base, &data);
+        image
+    }
+
+    /// Copies `data` in at `addr`, over anything already there.
+    pub fn insert(&mut self, addr: Address, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let end = addr + data.len();
+        // the segments which overlap or touch the new bytes all merge with them into one
+        let first = self.segments.partition_point(|seg| seg.end() < addr);
+        let last = self.segments.partition_point(|seg| seg.addr <= end);
+        let mut touching: Vec<Segment> = self.segments.drain(first..last).collect();
+        let start = touching.first().map_or(addr, |seg| seg.addr.min(addr));
+        let stop = touching.last().map_or(end, |seg| seg.end().max(end));
+
+        // appending to a segment is the usual case, so grow it in place rather than copying
+        let mut merged = match touching.first() {
+            Some(seg) if seg.addr == start => touching.remove(0).data,
+            _ => Vec::new(),
+        };
+        merged.resize(stop - start, 0);
+        for seg in &touching {
+            merged[seg.addr - start..seg.end() - start].copy_from_slice(&seg.data);
+        }
+        merged[addr - start..end - start].copy_from_slice(data);
+        self.segments.insert(first, Segment { addr: start, data: merged });
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn read(&self, addr: Address) -> Option<u8> {
+        self.segments.iter()
+            .find(|seg| (seg.addr..seg.end()).contains(&addr))
+            .map(|seg| seg.data[addr - seg.addr])
+    }
+
+    /// Lowest and one-past-highest loaded address.
+    pub fn bounds(&self) -> Option<(Address, Address)> {
+        Some((self.segments.first()?.addr, self.segments.last()?.end()))
+    }
+
+    /// Everything from the lowest to highest loaded address as one buffer, with gaps filled
+    /// by `fill`. Returns the buffer's base address along with it.
+    pub fn flatten(&self, fill: u8) -> (Address, Vec<u8>) {
+        let (start, end) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return (0, Vec::new()),
+        };
+        let mut flat = vec![fill; end - start];
+        for seg in &self.segments {
+            flat[seg.addr - start..seg.end() - start].copy_from_slice(&seg.data);
+        }
+        (start, flat)
+    }
+
+    /// Parses Intel HEX, including extended segment and linear address records.
+    pub fn from_ihex(text: &str) -> Result<Image, LoadError> {
+        let mut image = Image::default();
+        let mut upper = 0;
+
+        for (n, line) in text.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let record = line.strip_prefix(':').ok_or_else(|| syntax(n, "record doesn't start with `:`"))?;
+            let record = hex_bytes(n, record)?;
+            if record.len() < 5 || record.len() != record[0] as usize + 5 {
+                return Err(syntax(n, "record length doesn't match its byte count"));
+            }
+
+            let (body, checksum) = record.split_at(record.len() - 1);
+            let expected = body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)).wrapping_neg();
+            if checksum[0] != expected {
+                return Err(LoadError { line: n, kind: LoadErrorKind::Checksum { expected, found: checksum[0] } });
+            }
+
+            let offset = be_addr(&body[1..3]);
+            let data = &body[4..];
+            match body[3] {
+                0x00 => image.insert(upper + offset, data),
+                0x01 => break,
+                0x02 if data.len() == 2 => upper = be_addr(data) << 4,
+                0x04 if data.len() == 2 => upper = be_addr(data) << 16,
+                // CS:IP
+                0x03 if data.len() == 4 => image.entry = Some((be_addr(&data[..2]) << 4) + be_addr(&data[2..])),
+                0x05 if data.len() == 4 => image.entry = Some(be_addr(data)),
+                0x02..=0x05 => return Err(syntax(n, "wrong length for address record")),
+                _ => return Err(syntax(n, "unknown record type")),
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Parses Motorola S-records (S19, S28 and S37).
+    pub fn from_srec(text: &str) -> Result<Image, LoadError> {
+        let mut image = Image::default();
+
+        for (n, line) in text.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let kind = match line.strip_prefix('S').and_then(|rest| rest.chars().next()) {
+                Some(kind) => kind,
+                None => return Err(syntax(n, "record doesn't start with `S`")),
+            };
+            let record = hex_bytes(n, line.get(2..).unwrap_or(""))?;
+            if record.is_empty() || record.len() != record[0] as usize + 1 {
+                return Err(syntax(n, "record length doesn't match its byte count"));
+            }
+
+            let (body, checksum) = record.split_at(record.len() - 1);
+            let expected = !body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+            if checksum[0] != expected {
+                return Err(LoadError { line: n, kind: LoadErrorKind::Checksum { expected, found: checksum[0] } });
+            }
+
+            let addr_len = match kind {
+                '0' | '1' | '5' | '9' => 2,
+                '2' | '6' | '8' => 3,
+                '3' | '7' => 4,
+                _ => return Err(syntax(n, "unknown record type")),
+            };
+            if body.len() < 1 + addr_len {
+                return Err(syntax(n, "record too short for its address"));
+            }
+            let addr = be_addr(&body[1..1 + addr_len]);
+            let data = &body[1 + addr_len..];
+            match kind {
+                '1' | '2' | '3' => image.insert(addr, data),
+                '7' | '8' | '9' => image.entry = Some(addr),
+                // header and record counts
+                _ => {}
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Loads `path` in the format its extension suggests, putting flat binaries at `base`.
+    pub fn load<P>(path: P, base: Address) -> Result<Image, Box<dyn Error + Send + Sync>> where P: AsRef<Path> {
+        let path = path.as_ref();
+        match Format::from_path(path) {
+            Format::Binary => Ok(Image::raw(fs::read(path)?, base)),
+            Format::IntelHex => Ok(Image::from_ihex(&fs::read_to_string(path)?)?),
+            Format::Srec => Ok(Image::from_srec(&fs::read_to_string(path)?)?),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Binary,
+    IntelHex,
+    Srec,
+}
+
+impl Format {
+    /// Intel HEX for `.hex`/`.ihx`, S-records for `.s19`/`.s28`/`.s37`/`.srec`/`.mot`, and a
+    /// flat binary for anything else.
+    pub fn from_path<P>(path: P) -> Format where P: AsRef<Path> {
+        let ext = path.as_ref().extension().and_then(|ext| ext.to_str()).map(str::to_lowercase);
+        match ext.as_deref() {
+            Some("hex") | Some("ihx") => Format::IntelHex,
+            Some("s19") | Some("s28") | Some("s37") | Some("srec") | Some("mot") => Format::Srec,
+            _ => Format::Binary,
+        }
+    }
+}
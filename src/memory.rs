@@ -0,0 +1,126 @@
+//! What the CPU sees: ROM, RAM and memory-mapped I/O regions at their addresses, with
+//! unmapped holes between them.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::loader::Image;
+use crate::printer::Address;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    Rom,
+    Ram,
+    /// Memory-mapped I/O, where reads may have side effects
+    Mmio,
+    /// Nothing answers here
+    Unmapped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub base: Address,
+    pub len: usize,
+    /// Contents, if known. Empty for uninitialised RAM and MMIO.
+    pub data: Vec<u8>,
+    /// Whether we may read (and so decode) from here.
+    pub readable: bool,
+}
+
+impl Region {
+    pub fn rom(base: Address, data: Vec<u8>) -> Region {
+        Region { kind: RegionKind::Rom, base, len: data.len(), data, readable: true }
+    }
+
+    /// RAM with unknown contents.
+    pub fn ram(base: Address, len: usize) -> Region {
+        Region { kind: RegionKind::Ram, base, len, data: Vec::new(), readable: true }
+    }
+
+    pub fn mmio(base: Address, len: usize) -> Region {
+        Region { kind: RegionKind::Mmio, base, len, data: Vec::new(), readable: false }
+    }
+
+    pub fn end(&self) -> Address {
+        self.base + self.len
+    }
+
+    pub fn contains(&self, addr: Address) -> bool {
+        (self.base..self.end()).contains(&addr)
+    }
+
+    /// The byte at `addr`, if it's readable and its contents are known.
+    pub fn read(&self, addr: Address) -> Option<u8> {
+        if !self.readable {
+            return None;
+        }
+        addr.checked_sub(self.base).and_then(|offset| self.data.get(offset)).copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlapError {
+    pub addr: Address,
+}
+
+impl fmt::Display for OverlapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "region overlaps another at {:#06x}", self.addr)
+    }
+}
+
+impl Error for OverlapError {}
+
+/// Non-overlapping regions in address order. Addresses outside all of them are unmapped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryImage {
+    regions: Vec<Region>,
+}
+
+impl MemoryImage {
+    /// A single ROM holding `data` at `base`.
+    pub fn rom(data: Vec<u8>, base: Address) -> MemoryImage {
+        MemoryImage { regions: vec![Region::rom(base, data)] }
+    }
+
+    /// Adds `region`, which mustn't overlap any already mapped.
+    pub fn map(&mut self, region: Region) -> Result<(), OverlapError> {
+        if let Some(other) = self.regions.iter().find(|r| r.base < region.end() && region.base < r.end()) {
+            return Err(OverlapError { addr: other.base.max(region.base) });
+        }
+        let i = self.regions.partition_point(|r| r.base < region.base);
+        self.regions.insert(i, region);
+        Ok(())
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn region(&self, addr: Address) -> Option<&Region> {
+        self.regions.iter().find(|r| r.contains(addr))
+    }
+
+    pub fn kind(&self, addr: Address) -> RegionKind {
+        self.region(addr).map_or(RegionKind::Unmapped, |r| r.kind)
+    }
+
+    pub fn read(&self, addr: Address) -> Option<u8> {
+        self.region(addr).and_then(|r| r.read(addr))
+    }
+
+    /// Up to `len` readable bytes from `addr`, stopping short at anything unreadable.
+    pub fn fetch(&self, addr: Address, len: usize) -> Vec<u8> {
+        (addr..addr + len).map_while(|a| self.read(a)).collect()
+    }
+}
+
+impl From<&Image> for MemoryImage {
+    /// Each of the image's segments as ROM.
+    fn from(image: &Image) -> MemoryImage {
+        MemoryImage {
+            regions: image.segments().iter().map(|seg| Region::rom(seg.addr, seg.data.clone())).collect(),
+        }
+    }
+}
@@ -1,11 +1,263 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::io::{self, Write};
+
 use colored::Colorize;
 
+use crate::data::{DataFormat, Directive, Line};
+use crate::flow::Flow;
+use crate::json;
+use crate::layout::{Column, HexStyle, Layout};
+use crate::memory::MemoryImage;
+use crate::symbols::{SymbolKind, Symbols};
+use crate::syntax::Syntax;
+use crate::xref::Xrefs;
+
+/// Operands, each with its offset into the encoding.
+pub type Operands<O> = Vec<(usize, O)>;
+
 pub trait Print {
+    /// What [`Print::code`] gives operands as.
+    type Operand;
+    /// Which processor [`Print::timing`] is for, where that makes a difference.
+    type Cpu: Copy;
+
     fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write;
+
+    /// Like [`Print::print`], but naming addresses and ports in operands from `symbols`
+    /// where possible.
+    fn print_labelled<W>(&self, w: &mut W, _symbols: &Symbols) -> io::Result<()> where W: Write {
+        self.print(w)
+    }
+
+    /// Like [`Print::print_labelled`], but in `syntax`; [`Syntax::Raw`] should print the
+    /// same.
+    fn print_syntax<W>(&self, w: &mut W, symbols: &Symbols, _syntax: Syntax) -> io::Result<()> where W: Write {
+        self.print_labelled(w, symbols)
+    }
+
+    /// Operands, each with its offset into the encoding, and control flow; `None` for data.
+    fn code(&self) -> Option<(Operands<Self::Operand>, Flow)> {
+        None
+    }
+
+    /// Addresses this refers to which deserve a label.
+    fn references(&self) -> Vec<(Address, LabelKind)> {
+        Vec::new()
+    }
+
+    /// Execution time on `cpu`, for the cycles column.
+    fn timing(&self, _cpu: Self::Cpu) -> Option<String> {
+        None
+    }
+
+    /// The bytes this stands for, if it's data rather than code, so neighbouring data can be
+    /// laid out together.
+    fn data(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// The bytes this stands for, for the bytes column.
+    fn bytes(&self) -> Vec<u8> {
+        self.data().map(<[u8]>::to_vec).unwrap_or_default()
+    }
+}
+
+/// A line of a listing: either a decoded instruction, or bytes which didn't decode as one.
+pub enum Entry<I> {
+    Code(I),
+    Data(Vec<u8>),
+}
+
+impl<I> Print for Entry<I> where I: Print {
+    type Operand = I::Operand;
+    type Cpu = I::Cpu;
+
+    fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        match self {
+            Entry::Code(instr) => instr.print(w),
+            Entry::Data(_) => self.print_labelled(w, &Symbols::default()),
+        }
+    }
+
+    fn print_syntax<W>(&self, w: &mut W, symbols: &Symbols, syntax: Syntax) -> io::Result<()> where W: Write {
+        match self {
+            Entry::Code(instr) => instr.print_syntax(w, symbols, syntax),
+            Entry::Data(_) => self.print_labelled(w, symbols),
+        }
+    }
+
+    fn print_labelled<W>(&self, w: &mut W, symbols: &Symbols) -> io::Result<()> where W: Write {
+        match self {
+            Entry::Code(instr) => instr.print_labelled(w, symbols),
+            Entry::Data(bytes) => {
+                write!(w, "db ")?;
+                for (i, b) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, ", ")?;
+                    }
+                    write!(w, "{:#04x}", b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn code(&self) -> Option<(Operands<I::Operand>, Flow)> {
+        match self {
+            Entry::Code(instr) => instr.code(),
+            Entry::Data(_) => None,
+        }
+    }
+
+    fn timing(&self, cpu: I::Cpu) -> Option<String> {
+        match self {
+            Entry::Code(instr) => instr.timing(cpu),
+            Entry::Data(_) => None,
+        }
+    }
+
+    fn references(&self) -> Vec<(Address, LabelKind)> {
+        match self {
+            Entry::Code(instr) => instr.references(),
+            Entry::Data(_) => Vec::new(),
+        }
+    }
+
+    fn data(&self) -> Option<&[u8]> {
+        match self {
+            Entry::Code(_) => None,
+            Entry::Data(bytes) => Some(bytes),
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Entry::Code(instr) => instr.bytes(),
+            Entry::Data(bytes) => bytes.clone(),
+        }
+    }
+}
+
+pub type Address = usize;
+
+/// A bank, by its index in the [`BankMap`](crate::i8085::memory::BankMap).
+pub type BankId = u8;
+
+/// An address, qualified by the bank it's in where memory is banked: the same address in
+/// two banks is two different places.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BankedAddress {
+    pub bank: Option<BankId>,
+    pub addr: Address,
+}
+
+impl BankedAddress {
+    pub fn new(bank: Option<BankId>, addr: Address) -> BankedAddress {
+        BankedAddress { bank, addr }
+    }
+
+    /// `addr`, in the same bank as this.
+    pub fn with_addr(self, addr: Address) -> BankedAddress {
+        BankedAddress { addr, ..self }
+    }
+}
+
+impl From<Address> for BankedAddress {
+    fn from(addr: Address) -> BankedAddress {
+        BankedAddress { bank: None, addr }
+    }
+}
+
+impl fmt::Display for BankedAddress {
+    /// `bank:addr`, or just `addr` if it isn't banked.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{:02x}:{:04x}", bank, self.addr),
+            None => write!(f, "{:04x}", self.addr),
+        }
+    }
+}
+
+/// Why an address gets a generated label. When several apply, the greatest wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LabelKind {
+    /// Jumped to
+    Location,
+    /// Called
+    Subroutine,
+    /// Entered through restart vector `n`
+    Vector(u8),
+}
+
+impl LabelKind {
+    /// `loc_4000`, or `loc_01_4000` in bank 1.
+    pub fn name(&self, at: BankedAddress) -> String {
+        let bank = at.bank.map_or(String::new(), |bank| format!("{:02x}_", bank));
+        match self {
+            LabelKind::Location => format!("loc_{}{:04x}", bank, at.addr),
+            LabelKind::Subroutine => format!("sub_{}{:04x}", bank, at.addr),
+            LabelKind::Vector(n) => format!("rst_vec_{}{}", bank, n),
+        }
+    }
+}
+
+/// Labels for the targets of `instructions` which are themselves in `instructions`. Targets
+/// are looked for in the referring instruction's bank first, then in any bank, so long as
+/// only one has an instruction there.
+pub fn generate_labels<'a, I, L>(instructions: L) -> Symbols
+    where I: Print + 'a, L: IntoIterator<Item = &'a (BankedAddress, I)>
+{
+    let instructions: Vec<&(BankedAddress, I)> = instructions.into_iter().collect();
+    let starts = Starts::new(instructions.iter().map(|(at, _)| *at));
+
+    let mut kinds: BTreeMap<BankedAddress, LabelKind> = BTreeMap::new();
+    for (at, instr) in instructions {
+        for (target, kind) in instr.references() {
+            let target = match starts.resolve_target(*at, target) {
+                Some(target) => target,
+                None => continue,
+            };
+            let best = kinds.entry(target).or_insert(kind);
+            *best = kind.max(*best);
+        }
+    }
+
+    let mut labels = Symbols::default();
+    for (at, kind) in kinds {
+        labels.insert(at, &kind.name(at), SymbolKind::Code);
+    }
+    labels
+}
+
+/// Where instructions start, in each bank, for working out which bank a jump or call from
+/// one of them lands in.
+pub(crate) struct Starts {
+    banks: HashMap<Address, Vec<BankedAddress>>,
+}
+
+impl Starts {
+    pub(crate) fn new<I>(starts: I) -> Starts where I: IntoIterator<Item = BankedAddress> {
+        let mut banks: HashMap<Address, Vec<BankedAddress>> = HashMap::new();
+        for at in starts {
+            banks.entry(at.addr).or_default().push(at);
+        }
+        Starts { banks }
+    }
+
+    /// `target` in the same bank as `from` if an instruction starts there, otherwise in
+    /// whichever bank has one, if only one does.
+    pub(crate) fn resolve_target(&self, from: BankedAddress, target: Address) -> Option<BankedAddress> {
+        let banks = self.banks.get(&target)?;
+        let same = from.with_addr(target);
+        match banks.as_slice() {
+            _ if banks.contains(&same) => Some(same),
+            [only] => Some(*only),
+            _ => None,
+        }
+    }
 }
 
-type Address = usize;
 pub enum AddressWidth {
     Bits16,
     Bits32,
@@ -13,17 +265,36 @@ pub enum AddressWidth {
 }
 
 pub struct Printer<I> where I: Print {
-    instructions: Vec<(Address, I)>,
+    instructions: Vec<(BankedAddress, I)>,
     address_width: AddressWidth,
+    bank_names: Vec<String>,
     color: bool,
+    /// Which processor to show cycles for, if any
+    cycles: Option<I::Cpu>,
+    auto_labels: bool,
+    symbols: Symbols,
+    xrefs: Xrefs,
+    data: DataFormat,
+    layout: Layout,
+    syntax: Syntax,
+    memory: Option<MemoryImage>,
 }
 
 impl<I> Printer<I> where I: Print {
-    pub fn new(instructions: Vec<(Address, I)>, address_width: AddressWidth) -> Printer<I> {
+    pub fn new<A>(instructions: Vec<(A, I)>, address_width: AddressWidth) -> Printer<I> where A: Into<BankedAddress> {
         Printer {
-            instructions,
+            instructions: instructions.into_iter().map(|(at, instr)| (at.into(), instr)).collect(),
             address_width,
+            bank_names: Vec::new(),
             color: false,
+            cycles: None,
+            auto_labels: false,
+            symbols: Symbols::default(),
+            xrefs: Xrefs::default(),
+            data: DataFormat::default(),
+            layout: Layout::default(),
+            syntax: Syntax::default(),
+            memory: None,
         }
     }
 
@@ -32,20 +303,347 @@ impl<I> Printer<I> where I: Print {
         self
     }
 
+    /// Show how long each instruction takes on `cpu`, before its mnemonic.
+    pub fn with_cycles(mut self, cpu: I::Cpu) -> Printer<I> {
+        self.cycles = Some(cpu);
+        self
+    }
+
+    /// Generate labels for branch and call targets, and use them in operands.
+    pub fn with_labels(mut self) -> Printer<I> {
+        self.auto_labels = true;
+        self
+    }
+
+    /// Name `at` as code, overriding any generated label.
+    pub fn with_name<A>(mut self, at: A, name: &str) -> Printer<I> where A: Into<BankedAddress> {
+        self.symbols.insert(at, name, SymbolKind::Code);
+        self
+    }
+
+    /// Show banks by name (indexed by [`BankId`]) rather than number.
+    pub fn with_bank_names(mut self, names: Vec<String>) -> Printer<I> {
+        self.bank_names = names;
+        self
+    }
+
+    /// Use `symbols` for operands, labels and comments, overriding any generated labels.
+    pub fn with_symbols(mut self, symbols: Symbols) -> Printer<I> {
+        self.symbols.merge(symbols);
+        self
+    }
+
+    /// Note what refers to each address, above its line.
+    pub fn with_xrefs(mut self, xrefs: Xrefs) -> Printer<I> {
+        self.xrefs = xrefs;
+        self
+    }
+
+    /// Lay out data as `format` says, rather than the default.
+    pub fn with_data(mut self, format: DataFormat) -> Printer<I> {
+        self.data = format;
+        self
+    }
+
+    /// Lay lines out as `layout` says, rather than as address, mnemonic, operands and comment.
+    pub fn with_layout(mut self, layout: Layout) -> Printer<I> {
+        self.layout = layout;
+        self
+    }
+
+    /// Write instructions in `syntax`. Source is always written in Intel's, for AS.
+    pub fn with_syntax(mut self, syntax: Syntax) -> Printer<I> {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Show bytes as read from `mem`, rather than as each line encodes, which may differ for
+    /// opcodes with aliases.
+    pub fn with_memory(mut self, mem: MemoryImage) -> Printer<I> {
+        self.memory = Some(mem);
+        self
+    }
+
+    /// The symbols in effect: generated labels (if enabled), then user symbols.
+    pub fn symbols(&self) -> Symbols {
+        let mut symbols = if self.auto_labels {
+            generate_labels(&self.instructions)
+        } else {
+            Symbols::default()
+        };
+        symbols.merge(self.symbols.clone());
+        symbols
+    }
+
+    fn address(&self, at: BankedAddress) -> String {
+        let bank = match at.bank {
+            Some(bank) => match self.bank_names.get(bank as usize) {
+                Some(name) => format!("{}:", name),
+                None => format!("{}:", self.layout.hex(bank as usize, 2)),
+            },
+            None => String::new(),
+        };
+        let digits = match self.address_width {
+            AddressWidth::Bits16 => 4,
+            AddressWidth::Bits32 => 8,
+            AddressWidth::Bits64 => 16,
+        };
+        format!("{}{}", bank, self.layout.hex(at.addr, digits))
+    }
+
+    /// `line`'s bytes, as read from memory if there is any.
+    fn line_bytes<P>(&self, at: BankedAddress, line: &P) -> Vec<u8> where P: Print {
+        let bytes = line.bytes();
+        match &self.memory {
+            Some(mem) => mem.fetch(at.addr, bytes.len()),
+            None => bytes,
+        }
+    }
+
+    fn bytes<P>(&self, at: BankedAddress, line: &P) -> String where P: Print {
+        let bytes = self.line_bytes(at, line);
+        let mut text: Vec<String> = bytes.iter().take(self.layout.bytes).map(|&b| self.layout.hex(b as usize, 2)).collect();
+        if bytes.len() > self.layout.bytes {
+            text.push("+".to_string());
+        }
+        text.join(" ")
+    }
+
+    /// Joins `cells`, padding each to its column's width, with `indent` before the one at
+    /// `body`. Mnemonics are followed by a single space, as in source; other columns are four
+    /// apart.
+    fn join(&self, cells: &[(Column, String)], body: usize, indent: &str) -> String {
+        let mut line = String::new();
+        for (i, (column, cell)) in cells.iter().enumerate() {
+            if i == body {
+                line.push_str(indent);
+            }
+            let width = match column {
+                Column::Bytes => self.layout.bytes_width(),
+                Column::Label => self.layout.label_width,
+                Column::Cycles => 5,
+                Column::Mnemonic => self.layout.mnemonic_width,
+                Column::Operands => self.layout.operands_width,
+                Column::Address | Column::Comment => 0,
+            };
+            let cell = match column {
+                Column::Cycles => format!("{:>5}", cell),
+                _ => format!("{:<width$}", cell, width = width),
+            };
+            let cell = match column {
+                Column::Bytes if self.color => cell.green().to_string(),
+                Column::Mnemonic if self.color => cell.purple().to_string(),
+                _ => cell,
+            };
+            line.push_str(&cell);
+            match (column, cells.get(i + 1)) {
+                (_, None) => {}
+                (Column::Mnemonic, Some((Column::Operands, _))) => line.push(' '),
+                _ => line.push_str("    "),
+            }
+        }
+        line.trim_end().to_string()
+    }
+
+    /// The listing's lines, as every output lays them out: data runs are split wherever a
+    /// label or xref comment from `symbols` has to go.
+    fn lines(&self, symbols: &Symbols) -> Vec<(BankedAddress, Line<'_, I>)> {
+        self.data.lines(&self.instructions, |at| symbols.name(at).is_some() || self.xrefs.to(at).next().is_some())
+    }
+
+    /// The layout's columns, with cycles added if asked for.
+    fn columns(&self) -> Vec<Column> {
+        let mut columns = self.layout.columns.clone();
+        if self.cycles.is_some() && !columns.contains(&Column::Cycles) {
+            let at = columns.iter().position(|&c| c == Column::Mnemonic).unwrap_or(columns.len());
+            columns.insert(at, Column::Cycles);
+        }
+        columns
+    }
+
     pub fn print<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
-        for (addr, instr) in &self.instructions {
-            match self.address_width {
-                AddressWidth::Bits16 => write!(w, "{:04x}", addr)?,
-                AddressWidth::Bits32 => write!(w, "{:08x}", addr)?,
-                AddressWidth::Bits64 => write!(w, "{:016x}", addr)?,
+        let symbols = self.symbols();
+        let labelled = self.auto_labels || symbols.iter().next().is_some();
+        let columns = self.columns();
+        let inline_labels = columns.contains(&Column::Label);
+        // without a label column, labels go on a line of their own, starting where the first
+        // of these would, and lines under them are indented
+        let body = columns.iter()
+            .position(|c| matches!(c, Column::Cycles | Column::Mnemonic | Column::Operands | Column::Comment))
+            .unwrap_or(columns.len());
+        let indent = if labelled && !inline_labels { "    " } else { "" };
+        // operands name things as seen from the instruction's bank
+        let mut in_bank: HashMap<Option<BankId>, Symbols> = HashMap::new();
+
+        let lines = self.lines(&symbols);
+
+        for (at, line) in &lines {
+            let margin = |text: String, indent: &str| {
+                let mut cells: Vec<(Column, String)> = columns[..body].iter()
+                    .map(|&c| (c, if c == Column::Address { self.address(*at) } else { String::new() }))
+                    .collect();
+                cells.push((Column::Comment, text));
+                self.join(&cells, body, indent)
+            };
+            let label = symbols.name(*at);
+            if let Some(name) = label.filter(|_| !inline_labels) {
+                writeln!(w, "{}", margin(format!("{}:", name), ""))?;
+            }
+            if let Some(xrefs) = self.xrefs.comment(*at, &symbols) {
+                writeln!(w, "{}", margin(format!("; {}", xrefs), if inline_labels { "" } else { indent }))?;
+            }
+
+            let operands = in_bank.entry(at.bank).or_insert_with(|| symbols.in_bank(at.bank));
+            let mut asm = Vec::new();
+            line.print_syntax(&mut asm, operands, self.syntax)?;
+            let asm = self.layout.hex.restyle(&String::from_utf8_lossy(&asm), self.layout.uppercase);
+            let (mnemonic, args) = asm.split_once(' ').unwrap_or((&asm, ""));
+
+            let mut cells = Vec::new();
+            for &column in &columns {
+                let cell = match column {
+                    Column::Address => self.address(*at),
+                    Column::Bytes => self.bytes(*at, line),
+                    Column::Label => label.map_or(String::new(), |name| format!("{}:", name)),
+                    Column::Cycles => format!("{:>5}", self.cycles.and_then(|cpu| line.timing(cpu)).unwrap_or_default()),
+                    Column::Mnemonic => mnemonic.to_string(),
+                    Column::Operands => args.to_string(),
+                    Column::Comment => symbols.comment(*at).map_or(String::new(), |c| format!("; {}", c)),
+                };
+                cells.push((column, cell));
             }
+            writeln!(w, "{}", self.join(&cells, body, indent))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the listing as source for AS, for `cpu`: a `CPU` line, an `ORG` wherever the
+    /// address jumps, labels, and `EQU`s for names which aren't the start of a line, such as
+    /// variables and ports. Hex is `h`-suffixed, whatever the layout says. Lines whose bytes
+    /// wouldn't assemble back the same, like opcodes with aliases, are written as `db` with
+    /// the instruction in a comment; that needs [`Printer::with_memory`].
+    pub fn print_source<W>(&self, w: &mut W, cpu: &str) -> io::Result<()> where W: Write {
+        let symbols = self.symbols();
+        let restyle = |text: &[u8]| HexStyle::Suffix.restyle(&String::from_utf8_lossy(text), self.layout.uppercase);
+        let number = |value: Address| HexStyle::Suffix.mark(&self.layout.hex(value, 1));
+        let mut in_bank: HashMap<Option<BankId>, Symbols> = HashMap::new();
 
-            write!(w, "    ")?;
-            instr.print(w)?;
+        let lines = self.lines(&symbols);
+        let starts: BTreeSet<BankedAddress> = lines.iter().map(|(at, _)| *at).collect();
+
+        writeln!(w, "\tCPU {}", cpu.to_uppercase())?;
+        for (at, symbol) in symbols.iter().filter(|(at, _)| !starts.contains(at)) {
+            writeln!(w, "{}\tEQU {}", symbol.name, number(at.addr))?;
+        }
+        for (port, name) in symbols.ports() {
+            writeln!(w, "{}\tEQU {}", name, number(port))?;
+        }
+
+        let mut next = None;
+        for (at, line) in &lines {
+            if next != Some(*at) {
+                writeln!(w, "\tORG {}", number(at.addr))?;
+            }
+            let bytes = line.bytes();
+            next = Some(at.with_addr(at.addr + bytes.len()));
 
+            if let Some(name) = symbols.name(*at) {
+                writeln!(w, "{}:", name)?;
+            }
+            if let Some(xrefs) = self.xrefs.comment(*at, &symbols) {
+                writeln!(w, "\t; {}", xrefs)?;
+            }
+
+            let operands = in_bank.entry(at.bank).or_insert_with(|| symbols.in_bank(at.bank));
+            let mut asm = Vec::new();
+            line.print_syntax(&mut asm, operands, Syntax::Intel)?;
+            let asm = restyle(&asm);
+            let actual = self.memory.as_ref().map(|mem| mem.fetch(at.addr, bytes.len()));
+            match actual.filter(|actual| *actual != bytes) {
+                Some(actual) => {
+                    let mut db = Vec::new();
+                    Directive::Bytes(actual).print(&mut db)?;
+                    write!(w, "\t{}\t; {}", restyle(&db), asm)?;
+                }
+                None => write!(w, "\t{}", asm)?,
+            }
+            if let Some(comment) = symbols.comment(*at) {
+                write!(w, "\t; {}", comment)?;
+            }
             writeln!(w)?;
         }
 
         Ok(())
     }
 }
+
+/// JSON needs to know how to describe operands.
+impl<I> Printer<I> where I: Print, I::Operand: json::ToOperand {
+    /// The listing as a JSON segment, lines laid out as [`Printer::print`] would.
+    pub fn segment(&self) -> json::Segment {
+        let symbols = self.symbols();
+        let mut in_bank: HashMap<Option<BankId>, Symbols> = HashMap::new();
+        let lines = self.lines(&symbols);
+
+        let mut records = Vec::new();
+        for (at, line) in &lines {
+            let operands = in_bank.entry(at.bank).or_insert_with(|| symbols.in_bank(at.bank));
+            let mut asm = Vec::new();
+            line.print_syntax(&mut asm, operands, self.syntax).expect("writing to a Vec can't fail");
+            let text = self.layout.hex.restyle(&String::from_utf8_lossy(&asm), self.layout.uppercase);
+            let mnemonic = text.split(' ').next().unwrap_or_default().to_string();
+            let code = line.code().map(|(code, flow)| json::Code::new(&code, flow, operands));
+            let comments = symbols.comment(*at).map(str::to_string).into_iter()
+                .chain(self.xrefs.comment(*at, &symbols))
+                .collect();
+            records.push(json::Line {
+                address: at.addr,
+                bank: at.bank,
+                bytes: self.line_bytes(*at, line),
+                kind: if code.is_some() { json::LineKind::Code } else { json::LineKind::Data },
+                mnemonic,
+                text,
+                operands: code.as_ref().map(|code| code.operands.clone()).unwrap_or_default(),
+                flow: code.as_ref().map(|code| code.flow),
+                targets: code.map(|code| code.targets).unwrap_or_default(),
+                labels: symbols.name(*at).map(str::to_string).into_iter().collect(),
+                comments,
+            });
+        }
+
+        let bank = records.first().and_then(|line| line.bank);
+        json::Segment {
+            bank,
+            bank_name: bank.and_then(|bank| self.bank_names.get(bank as usize).cloned()),
+            start: records.first().map_or(0, |line| line.address),
+            end: records.last().map_or(0, |line| line.address + line.bytes.len()),
+            lines: records,
+        }
+    }
+
+    /// Writes the listing as a JSON [`json::Document`] for `cpu`, with its symbols and
+    /// xrefs.
+    pub fn print_json<W>(&self, w: &mut W, cpu: &str) -> io::Result<()> where W: Write {
+        let document = json::Document::new(cpu, vec![self.segment()], &self.symbols(), &self.xrefs);
+        serde_json::to_writer_pretty(&mut *w, &document)?;
+        writeln!(w)
+    }
+
+    /// Writes the listing as JSON lines for `cpu`: a [`json::Header`], then each
+    /// [`json::Line`] of [`Printer::segment`], bank and all, compactly on a line of its own.
+    pub fn print_jsonl<W>(&self, w: &mut W, cpu: &str) -> io::Result<()> where W: Write {
+        serde_json::to_writer(&mut *w, &json::Header::new(cpu, self.bank_names.clone()))?;
+        writeln!(w)?;
+        self.print_jsonl_lines(w)
+    }
+
+    /// Writes just the lines of [`Printer::print_jsonl`], to follow on from another listing's.
+    pub fn print_jsonl_lines<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        for line in self.segment().lines {
+            serde_json::to_writer(&mut *w, &line)?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//! Names for addresses, I/O ports and comments, and the files they're kept in.
+//!
+//! Our own format has one symbol per line, `#` starting a comment:
+//!
+//! ```text
+//! code    0x0000  reset
+//! data    0xf800  tick_count
+//! port    0x10    uart_data
+//! comment 0x0042  waits for the UART to drain
+//! code    0x01:0x4000  banked_print
+//! ```
+//!
+//! where an address may be prefixed with the bank it's in, for banked memory.
+//!
+//! Symbol tables from AS listings (`.lst`) and MAME debugger comment files (`.cmt`) can be
+//! imported too.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::printer::{Address, BankId, BankedAddress};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// A label on code
+    Code,
+    /// A variable or table
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Symbols {
+    addresses: BTreeMap<BankedAddress, Symbol>,
+    ports: BTreeMap<Address, String>,
+    comments: BTreeMap<BankedAddress, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SymbolError {}
+
+/// Parses `0x1337`, `$1337` or `1337h` as hex, anything else as decimal.
+fn parse_number(s: &str) -> Option<Address> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        Address::from_str_radix(hex, 16).ok()
+    } else if let Some(hex) = s.strip_suffix('h') {
+        Address::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Parses an address as [`parse_number`] does, optionally preceded by `bank:`.
+fn parse_location(s: &str) -> Option<BankedAddress> {
+    match s.split_once(':') {
+        Some((bank, addr)) => {
+            let bank = BankId::try_from(parse_number(bank)?).ok()?;
+            Some(BankedAddress::new(Some(bank), parse_number(addr)?))
+        }
+        None => parse_number(s).map(BankedAddress::from),
+    }
+}
+
+fn format_location(at: BankedAddress) -> String {
+    match at.bank {
+        Some(bank) => format!("{:#04x}:{:#06x}", bank, at.addr),
+        None => format!("{:#06x}", at.addr),
+    }
+}
+
+/// Splits off the first whitespace-separated word.
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(i) => (&s[..i], s[i..].trim()),
+        None => (s, ""),
+    }
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The value of `attr="..."` within an XML tag.
+fn xml_attr<'a>(tag: &'a str, attr: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}=\"", attr))? + attr.len() + 2;
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+impl Symbols {
+    /// The symbol at `at`; for banked addresses, one given without a bank will do.
+    pub fn symbol<A>(&self, at: A) -> Option<&Symbol> where A: Into<BankedAddress> {
+        let at = at.into();
+        self.addresses.get(&at).or_else(|| self.addresses.get(&BankedAddress::from(at.addr)))
+    }
+
+    pub fn name<A>(&self, at: A) -> Option<&str> where A: Into<BankedAddress> {
+        self.symbol(at).map(|sym| sym.name.as_str())
+    }
+
+    pub fn port(&self, port: Address) -> Option<&str> {
+        self.ports.get(&port).map(String::as_str)
+    }
+
+    /// The comment at `at`, falling back like [`Symbols::symbol`].
+    pub fn comment<A>(&self, at: A) -> Option<&str> where A: Into<BankedAddress> {
+        let at = at.into();
+        self.comments.get(&at).or_else(|| self.comments.get(&BankedAddress::from(at.addr))).map(String::as_str)
+    }
+
+    pub fn insert<A>(&mut self, at: A, name: &str, kind: SymbolKind) where A: Into<BankedAddress> {
+        self.addresses.insert(at.into(), Symbol { name: name.to_string(), kind });
+    }
+
+    pub fn insert_port(&mut self, port: Address, name: &str) {
+        self.ports.insert(port, name.to_string());
+    }
+
+    pub fn insert_comment<A>(&mut self, at: A, text: &str) where A: Into<BankedAddress> {
+        self.comments.insert(at.into(), text.to_string());
+    }
+
+    /// Adds everything from `other`, which wins where both name the same thing.
+    pub fn merge(&mut self, other: Symbols) {
+        self.addresses.extend(other.addresses);
+        self.ports.extend(other.ports);
+        self.comments.extend(other.comments);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.ports.is_empty() && self.comments.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BankedAddress, &Symbol)> {
+        self.addresses.iter().map(|(&at, sym)| (at, sym))
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = (Address, &str)> {
+        self.ports.iter().map(|(&port, name)| (port, name.as_str()))
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = (BankedAddress, &str)> {
+        self.comments.iter().map(|(&at, text)| (at, text.as_str()))
+    }
+
+    /// These symbols as seen from code in `bank`: those in it and those without a bank,
+    /// all keyed by plain address. For naming operands, which don't say which bank they
+    /// mean.
+    pub fn in_bank(&self, bank: Option<BankId>) -> Symbols {
+        let mut seen = Symbols { ports: self.ports.clone(), ..Symbols::default() };
+        // unbanked entries sort first, so the bank's own override them
+        for (at, sym) in self.addresses.iter().filter(|(at, _)| at.bank.is_none() || at.bank == bank) {
+            seen.addresses.insert(at.addr.into(), sym.clone());
+        }
+        for (at, text) in self.comments.iter().filter(|(at, _)| at.bank.is_none() || at.bank == bank) {
+            seen.comments.insert(at.addr.into(), text.clone());
+        }
+        seen
+    }
+
+    /// Parses our own symbol file format.
+    pub fn parse(text: &str) -> Result<Symbols, SymbolError> {
+        let mut symbols = Symbols::default();
+        for (n, line) in text.lines().enumerate() {
+            let err = |message: &str| SymbolError { line: n + 1, message: message.to_string() };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (kind, rest) = split_word(line);
+            let (addr, rest) = split_word(rest);
+            if addr.is_empty() {
+                return Err(err("missing address"));
+            }
+            let addr = parse_location(addr).ok_or_else(|| err("bad address"))?;
+            if rest.is_empty() {
+                return Err(err("missing name"));
+            }
+
+            match kind {
+                "code" => symbols.insert(addr, rest, SymbolKind::Code),
+                "data" => symbols.insert(addr, rest, SymbolKind::Data),
+                "port" if addr.bank.is_none() => symbols.insert_port(addr.addr, rest),
+                "port" => return Err(err("ports aren't banked")),
+                "comment" => symbols.insert_comment(addr, rest),
+                _ => return Err(err(&format!("unknown symbol kind `{}`", kind))),
+            }
+        }
+        Ok(symbols)
+    }
+
+    /// Imports the symbol table at the end of an AS listing. Symbols in the code segment
+    /// become code labels, data segment ones data, and I/O segment ones ports; plain
+    /// constants and anything non-numeric are skipped.
+    pub fn from_as_listing(text: &str) -> Symbols {
+        let mut symbols = Symbols::default();
+        let table = match text.to_ascii_lowercase().find("symbol table") {
+            Some(start) => &text[start..],
+            None => return symbols,
+        };
+
+        // entries look like ` NAME :   1337 C |`, a few to a line; unused ones start with `*`
+        for entry in table.lines().flat_map(|line| line.split('|')) {
+            let (name, value) = match entry.find(" : ") {
+                Some(i) => (entry[..i].trim().trim_start_matches('*'), entry[i + 3..].trim()),
+                None => continue,
+            };
+            let mut fields = value.split_whitespace();
+            let (value, segment) = match (fields.next(), fields.next()) {
+                (Some(value), Some(segment)) => (value, segment),
+                _ => continue,
+            };
+            let value = match Address::from_str_radix(value, 16) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            match segment {
+                "C" => symbols.insert(value, name, SymbolKind::Code),
+                "D" => symbols.insert(value, name, SymbolKind::Data),
+                "I" => symbols.insert_port(value, name),
+                _ => {}
+            }
+        }
+        symbols
+    }
+
+    /// Imports the comments from a MAME debugger comment file, for every CPU in it.
+    pub fn from_mame_comments(text: &str) -> Symbols {
+        let mut symbols = Symbols::default();
+        for element in text.split("<comment ").skip(1) {
+            let tag_end = match element.find('>') {
+                Some(i) => i,
+                None => continue,
+            };
+            let addr: Address = match xml_attr(&element[..tag_end], "address").and_then(|a| a.parse().ok()) {
+                Some(addr) => addr,
+                None => continue,
+            };
+            let body = &element[tag_end + 1..];
+            let body = &body[..body.find("</comment>").unwrap_or(body.len())];
+            symbols.insert_comment(addr, &unescape_xml(body.trim()));
+        }
+        symbols
+    }
+
+    /// Loads symbols from `path`, going by its extension: `.lst` for AS listings, `.cmt` for
+    /// MAME comments, and our own format otherwise.
+    pub fn load<P>(path: P) -> Result<Symbols, Box<dyn Error + Send + Sync>> where P: AsRef<Path> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("lst") => Ok(Symbols::from_as_listing(&text)),
+            Some("cmt") => Ok(Symbols::from_mame_comments(&text)),
+            _ => Ok(Symbols::parse(&text)?),
+        }
+    }
+
+    /// Writes these symbols out in our own format.
+    pub fn write<W>(&self, w: &mut W) -> io::Result<()> where W: Write {
+        for (addr, sym) in self.iter() {
+            let kind = match sym.kind {
+                SymbolKind::Code => "code",
+                SymbolKind::Data => "data",
+            };
+            writeln!(w, "{:<8}{}  {}", kind, format_location(addr), sym.name)?;
+        }
+        for (port, name) in self.ports() {
+            writeln!(w, "{:<8}{:#04x}    {}", "port", port, name)?;
+        }
+        for (addr, text) in self.comments() {
+            writeln!(w, "{:<8}{}  {}", "comment", format_location(addr), text)?;
+        }
+        Ok(())
+    }
+}
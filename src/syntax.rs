@@ -0,0 +1,31 @@
+//! Which assembler's notation instructions are written in.
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Syntax {
+    /// Intel mnemonics, but register pairs named in full (`lxi hl, 0x1337`), as the parser
+    /// takes them
+    #[default]
+    Raw,
+    /// Intel's own, with pairs named by their first register (`lxi h, 0x1337`), as AS takes it
+    Intel,
+    /// Zilog's Z80 mnemonics (`ld hl,0x1337`, `ld a,(hl)`)
+    Zilog,
+    /// TDL's and Microsoft MACRO-80's: Intel mnemonics in upper case (`LXI H,0x1337`)
+    Tdl,
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Syntax, String> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(Syntax::Raw),
+            "intel" => Ok(Syntax::Intel),
+            "zilog" | "z80" => Ok(Syntax::Zilog),
+            "tdl" | "m80" => Ok(Syntax::Tdl),
+            _ => Err(format!("unknown syntax {:?} (expected raw, intel, zilog or tdl)", s)),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Cross-references: which instructions jump to, call, read, write or point at each
+//! address, and which use each I/O port.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::printer::{Address, BankedAddress};
+use crate::symbols::Symbols;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum XrefKind {
+    Jump,
+    /// Including restarts
+    Call,
+    Read,
+    Write,
+    /// An address loaded as an immediate, which may or may not be used as a pointer
+    Pointer,
+    /// To a port, rather than memory
+    In,
+    /// To a port, rather than memory
+    Out,
+}
+
+impl XrefKind {
+    /// Whether `to` is a port number rather than a memory address.
+    pub fn is_io(&self) -> bool {
+        matches!(self, XrefKind::In | XrefKind::Out)
+    }
+}
+
+impl fmt::Display for XrefKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            XrefKind::Jump => "jump",
+            XrefKind::Call => "call",
+            XrefKind::Read => "read",
+            XrefKind::Write => "write",
+            XrefKind::Pointer => "pointer",
+            XrefKind::In => "in",
+            XrefKind::Out => "out",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A reference from the instruction at `from` to `to`, an address or (for I/O) a port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Xref {
+    pub to: BankedAddress,
+    pub from: BankedAddress,
+    pub kind: XrefKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Xrefs {
+    // sorted by target, so lookups are ranges
+    xrefs: BTreeSet<Xref>,
+}
+
+impl Xrefs {
+    pub fn insert(&mut self, xref: Xref) {
+        self.xrefs.insert(xref);
+    }
+
+    pub fn merge(&mut self, other: Xrefs) {
+        self.xrefs.extend(other.xrefs);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Xref> {
+        self.xrefs.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.xrefs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xrefs.is_empty()
+    }
+
+    fn targeting(&self, to: BankedAddress) -> impl Iterator<Item = &Xref> {
+        let first = Xref { to, from: BankedAddress::default(), kind: XrefKind::Jump };
+        self.xrefs.range(first..).take_while(move |x| x.to == to)
+    }
+
+    /// Everything referring to memory at `at`.
+    pub fn to<A>(&self, at: A) -> impl Iterator<Item = &Xref> where A: Into<BankedAddress> {
+        self.targeting(at.into()).filter(|x| !x.kind.is_io())
+    }
+
+    /// Every `in` and `out` of `port`.
+    pub fn to_port(&self, port: u8) -> impl Iterator<Item = &Xref> {
+        self.targeting(BankedAddress::from(port as Address)).filter(|x| x.kind.is_io())
+    }
+
+    /// Everything the instruction at `at` refers to.
+    pub fn from<A>(&self, at: A) -> impl Iterator<Item = &Xref> where A: Into<BankedAddress> {
+        let at = at.into();
+        self.xrefs.iter().filter(move |x| x.from == at)
+    }
+
+    /// `XREF: sub_0100 (call), 0234 (read)`, listing the references to `at` with their
+    /// sources named from `symbols`; `None` if there aren't any.
+    pub fn comment<A>(&self, at: A, symbols: &Symbols) -> Option<String> where A: Into<BankedAddress> {
+        let sources: Vec<String> = self.to(at)
+            .map(|x| match symbols.name(x.from) {
+                Some(name) => format!("{} ({})", name, x.kind),
+                None => format!("{} ({})", x.from, x.kind),
+            })
+            .collect();
+        if sources.is_empty() {
+            return None;
+        }
+        Some(format!("XREF: {}", sources.join(", ")))
+    }
+}
+
+impl Extend<Xref> for Xrefs {
+    fn extend<T>(&mut self, iter: T) where T: IntoIterator<Item = Xref> {
+        self.xrefs.extend(iter);
+    }
+}
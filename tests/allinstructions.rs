@@ -0,0 +1,70 @@
+//! Golden-file tests against `testdata/allinstructions.bin`.
+//!
+//! `allinstructions.asm` is generated by `allinstructions.py` and assembled with AS
+//! (`asl -cpu 8085undoc`, then `p2bin`). After growing the generator, regenerate both and
+//! then rewrite `allinstructions.expected` with `BLESS=1 cargo test --test allinstructions`.
+
+use std::env;
+use std::fs;
+
+use ripntear::i8085::Instruction;
+
+const BIN: &str = "testdata/allinstructions.bin";
+const ASM: &str = "testdata/allinstructions.asm";
+const EXPECTED: &str = "testdata/allinstructions.expected";
+
+fn decode_all() -> Vec<Instruction> {
+    let bin = fs::read(BIN).unwrap();
+    let mut instructions = Vec::new();
+    let mut i = 0;
+    while i < bin.len() {
+        let (len, instr) = Instruction::decode_one(&bin[i..], i)
+            .unwrap_or_else(|e| panic!("{}", e));
+        instructions.push(instr);
+        i += len;
+    }
+    instructions
+}
+
+/// The instructions in the AS source, skipping directives and labels.
+fn source() -> Vec<(usize, Instruction)> {
+    fs::read_to_string(ASM).unwrap()
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with('\t'))
+        .filter(|(_, line)| {
+            let line = line.trim().to_lowercase();
+            !line.starts_with("cpu ") && !line.starts_with("org ")
+        })
+        .map(|(n, line)| {
+            let instr = line.parse().unwrap_or_else(|e| panic!("{}:{}: {}", ASM, n + 1, e));
+            (n + 1, instr)
+        })
+        .collect()
+}
+
+#[test]
+fn decodes_like_source() {
+    let decoded = decode_all();
+    let source = source();
+    for ((line, expected), actual) in source.iter().zip(&decoded) {
+        assert_eq!(expected, actual, "{}:{}", ASM, line);
+    }
+    assert_eq!(source.len(), decoded.len(), "instruction count");
+}
+
+#[test]
+fn matches_golden_listing() {
+    let listing: String = decode_all().iter().map(|instr| instr.raw_asm() + "\n").collect();
+
+    if env::var_os("BLESS").is_some() {
+        fs::write(EXPECTED, &listing).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(EXPECTED).unwrap();
+    for (n, (expected, actual)) in expected.lines().zip(listing.lines()).enumerate() {
+        assert_eq!(expected, actual, "{}:{}", EXPECTED, n + 1);
+    }
+    assert_eq!(expected.lines().count(), listing.lines().count(), "line count");
+}
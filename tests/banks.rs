@@ -0,0 +1,57 @@
+use ripntear::i8085::memory::{BankMap, BitField, Selector, Window};
+use ripntear::RegionKind;
+
+#[test]
+fn pc8300_preset() {
+    let map = BankMap::pc8300(Selector::Port(0x00));
+    map.validate().unwrap();
+
+    let (bank, offset) = map.resolve(0x1234, 0b00_01).unwrap();
+    assert_eq!((bank.name.as_str(), offset), ("rom1", 0x1234));
+    let (bank, offset) = map.resolve(0x9000, 0b10_00).unwrap();
+    assert_eq!((bank.name.as_str(), offset), ("ram2", 0x1000));
+    assert!(map.resolve(0x9000, 0b01_00).is_none());
+}
+
+#[test]
+fn mapping() {
+    let file: Vec<u8> = (0..0x10000).map(|i| (i >> 8) as u8).collect();
+    let map = BankMap::pc8300(Selector::Port(0x00));
+
+    let mem = map.mapping(&file, 0b00_01);
+    assert_eq!(mem.kind(0x0000), RegionKind::Rom);
+    assert_eq!(mem.read(0x0100), Some(0x81));
+    assert_eq!(mem.kind(0x8000), RegionKind::Ram);
+    assert_eq!(mem.read(0x8000), None);
+
+    let mem = map.mapping(&file, 0b01_00);
+    assert_eq!(mem.read(0x0100), Some(0x01));
+    assert_eq!(mem.kind(0x8000), RegionKind::Unmapped);
+}
+
+#[test]
+fn validation() {
+    let mut map = BankMap::pc8300(Selector::Address(0xffff));
+    map.windows.push(Window::fixed(0x7000, 0x2000, "rom0"));
+    assert!(map.validate().is_err());
+
+    let mut map = BankMap::pc8300(Selector::Address(0xffff));
+    map.windows[1] = Window::new(0x8000, 0x8000, BitField::new(2, 1), &[Some("stdram"), Some("nope")]);
+    assert!(map.validate().unwrap_err().0.contains("nope"));
+
+    map.windows[1].banks.pop();
+    assert!(map.validate().is_err());
+
+    // the selector is a byte
+    let mut map = BankMap::pc8300(Selector::Address(0xffff));
+    map.windows[1].field = BitField::new(6, 3);
+    assert!(map.validate().unwrap_err().0.contains("past the selector"));
+}
+
+#[test]
+fn bit_fields() {
+    assert_eq!(BitField::new(2, 2).extract(0b1011_0100), 0b01);
+    assert_eq!(BitField::new(0, 8).extract(0xa5), 0xa5);
+    assert_eq!(BitField::new(4, 4).extract(0xa5), 0x0a);
+    assert_eq!(BitField::new(7, 1).mask(), 0x80);
+}
@@ -0,0 +1,78 @@
+use ripntear::i8085::{Cfg, Edge, EdgeKind, Tracer};
+use ripntear::{BankedAddress, MemoryImage};
+
+// 0000: call 0x0008
+// 0003: jmp 0x0003
+// 0006: db 0xff, 0xff
+// 0008: mvi a, 0x00
+// 000a: jz 0x000e
+// 000d: inr a
+// 000e: rnz
+// 000f: ret
+const PROGRAM: &[u8] = &[
+    0xcd, 0x08, 0x00, 0xc3, 0x03, 0x00, 0xff, 0xff,
+    0x3e, 0x00, 0xca, 0x0e, 0x00, 0x3c, 0xc0, 0xc9,
+];
+
+fn edge(from: usize, to: usize, kind: EdgeKind) -> Edge {
+    Edge { from: from.into(), to: to.into(), kind }
+}
+
+#[test]
+fn blocks_and_edges() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let cfg = Cfg::from_trace(&Tracer::new(&mem).trace(&[0]));
+
+    let starts: Vec<usize> = cfg.blocks().map(|b| b.start.addr).collect();
+    assert_eq!(starts, [0x00, 0x03, 0x08, 0x0d, 0x0e, 0x0f]);
+    assert_eq!(cfg.block(0x08.into()).unwrap().instructions.len(), 2);
+    assert_eq!(cfg.block_containing(0x0a.into()).unwrap().start, BankedAddress::from(0x08));
+    assert!(cfg.block_containing(0x06.into()).is_none());
+
+    let edges: Vec<Edge> = cfg.edges().copied().collect();
+    assert_eq!(edges, [
+        edge(0x00, 0x03, EdgeKind::Fallthrough),
+        edge(0x00, 0x08, EdgeKind::Call),
+        edge(0x03, 0x03, EdgeKind::Taken),
+        edge(0x08, 0x0d, EdgeKind::NotTaken),
+        edge(0x08, 0x0e, EdgeKind::Taken),
+        edge(0x0d, 0x0e, EdgeKind::Fallthrough),
+        edge(0x0e, 0x03, EdgeKind::Return),
+        edge(0x0e, 0x0f, EdgeKind::NotTaken),
+        edge(0x0f, 0x03, EdgeKind::Return),
+    ]);
+    assert_eq!(cfg.predecessors(0x0e.into()).count(), 2);
+}
+
+#[test]
+fn functions() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let cfg = Cfg::from_trace(&Tracer::new(&mem).trace(&[0]));
+
+    let entries: Vec<usize> = cfg.functions().map(|f| f.entry.addr).collect();
+    assert_eq!(entries, [0x00, 0x08]);
+    let blocks: Vec<usize> = cfg.function(0x08.into()).unwrap().blocks.iter().map(|at| at.addr).collect();
+    assert_eq!(blocks, [0x08, 0x0d, 0x0e, 0x0f]);
+    let blocks: Vec<usize> = cfg.function(0x00.into()).unwrap().blocks.iter().map(|at| at.addr).collect();
+    assert_eq!(blocks, [0x00, 0x03]);
+}
+
+#[test]
+fn interrupt_handlers() {
+    // 0000: ei
+    // 0001: jmp 0x0001
+    // 0038: push psw
+    // 0039: pop psw
+    // 003a: ei
+    // 003b: ret
+    let mut program = vec![0xfb, 0xc3, 0x01, 0x00];
+    program.resize(0x38, 0x00);
+    program.extend([0xf5, 0xf1, 0xfb, 0xc9]);
+    let mem = MemoryImage::rom(program, 0);
+    let cfg = Cfg::from_trace(&Tracer::new(&mem).trace(&[0x00, 0x38]));
+
+    // nothing calls the handler, but it's still a function
+    let entries: Vec<usize> = cfg.functions().map(|f| f.entry.addr).collect();
+    assert_eq!(entries, [0x00, 0x38]);
+    assert_eq!(cfg.function(0x38.into()).unwrap().blocks.len(), 1);
+}
@@ -0,0 +1,22 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use ripntear::i8085::memory::{Bank, BankMap, BitField, Selector, Window};
+use ripntear::BankId;
+
+pub const BOOT: BankId = 0;
+pub const A: BankId = 1;
+pub const B: BankId = 2;
+
+/// `boot` fixed at 0x0000, with `a` or `b` switched into 0x4000 by bit 0 of port 0x10. Each
+/// is 0x4000 bytes, in that order in the file.
+pub fn two_banks() -> BankMap {
+    BankMap {
+        selector: Selector::Port(0x10),
+        banks: vec![Bank::rom("boot", 0x4000, 0), Bank::rom("a", 0x4000, 0x4000), Bank::rom("b", 0x4000, 0x8000)],
+        windows: vec![
+            Window::fixed(0x0000, 0x4000, "boot"),
+            Window::new(0x4000, 0x4000, BitField::new(0, 1), &[Some("a"), Some("b")]),
+        ],
+    }
+}
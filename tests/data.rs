@@ -0,0 +1,72 @@
+use ripntear::i8085::Tracer;
+use ripntear::{AddressWidth, DataFormat, Directive, MemoryImage, Print, Printer, Terminator};
+
+fn text(directive: &Directive) -> String {
+    let mut out = Vec::new();
+    directive.print(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn layout(format: &DataFormat, bytes: &[u8]) -> Vec<(usize, String)> {
+    format.directives(bytes).iter().map(|(offset, d)| (*offset, text(d))).collect()
+}
+
+#[test]
+fn bytes_and_words() {
+    let bytes: Vec<u8> = (1..=10).collect();
+    assert_eq!(layout(&DataFormat::default(), &bytes), [
+        (0, "db 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08".to_string()),
+        (8, "db 0x09, 0x0a".to_string()),
+    ]);
+    let words = DataFormat { words: true, ..DataFormat::default() };
+    assert_eq!(layout(&words, &[0x34, 0x12, 0x01, 0x00, 0x07]), [
+        (0, "dw 0x1234, 0x0001".to_string()),
+        (4, "db 0x07".to_string()),
+    ]);
+}
+
+#[test]
+fn strings() {
+    let format = DataFormat::default();
+    assert_eq!(layout(&format, b"\x01HELLO\0\x02"), [
+        (0, "db 0x01".to_string()),
+        (1, "db \"HELLO\", 0".to_string()),
+        (7, "db 0x02".to_string()),
+    ]);
+    assert_eq!(layout(&format, b"READ\xd9"), [(0, "db \"READ\", 'Y'|0x80".to_string())]);
+    assert_eq!(layout(&format, b"PLAIN TEXT"), [(0, "db \"PLAIN TEXT\"".to_string())]);
+    // too short to be worth it
+    assert_eq!(layout(&format, b"AB\x01"), [(0, "db 0x41, 0x42, 0x01".to_string())]);
+    assert_eq!(format.directives(b"OKAY\0\x80")[0].1, Directive::String { text: "OKAY".to_string(), terminator: Terminator::Nul });
+}
+
+#[test]
+fn fills() {
+    let mut bytes = vec![0x3e, 0x00];
+    bytes.extend([0xff; 0x20]);
+    assert_eq!(layout(&DataFormat::default(), &bytes), [
+        (0, "db 0x3e, 0x00".to_string()),
+        (2, "db 32 dup (0xff)".to_string()),
+    ]);
+    // short runs stay as bytes
+    assert_eq!(layout(&DataFormat::default(), &[0xff; 3]), [(0, "db 0xff, 0xff, 0xff".to_string())]);
+}
+
+#[test]
+fn printed_around_labels() {
+    // 0000: lxi h, 0x0008
+    // 0003: jmp 0x0003
+    // 0006: data, with a string at 0008 and padding after it
+    let mut program = vec![0x21, 0x08, 0x00, 0xc3, 0x03, 0x00, 0x01, 0x02];
+    program.extend(b"ERROR\0");
+    program.extend([0xff; 10]);
+    let mem = MemoryImage::rom(program, 0);
+    let listing = Tracer::new(&mem).trace(&[0]).into_listing();
+
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16).with_labels().with_name(0x000a, "mid").print(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    // "ER" is too short for a string once the label splits it off
+    assert!(out.contains("0006        db 0x01, 0x02, 0x45, 0x52\n000a    mid:\n000a        db \"ROR\", 0\n"));
+    assert!(out.contains("000e        db 10 dup (0xff)\n"));
+}
@@ -0,0 +1,98 @@
+use ripntear::i8085::{ConditionCodes, Effects, Flags, Instruction, MemRef, Register, RegisterPair, Regs};
+
+const HL: Regs = Regs::from_bits_truncate(Regs::H.bits() | Regs::L.bits());
+const DE: Regs = Regs::from_bits_truncate(Regs::D.bits() | Regs::E.bits());
+
+#[test]
+fn mov_to_memory() {
+    let fx = Instruction::Mov { src: Register::B, dest: Register::Mem }.effects();
+    assert_eq!(fx.uses, Regs::B | HL);
+    assert_eq!(fx.defs, Regs::empty());
+    assert_eq!(fx.mem_written, Some(MemRef::Hl));
+    assert_eq!(fx.mem_read, None);
+    assert_eq!(fx.flags_defined, Flags::empty());
+}
+
+#[test]
+fn push_and_pop_psw() {
+    let push = Instruction::Push { reg_pair: RegisterPair::PSW }.effects();
+    assert_eq!(push.uses, Regs::A | Regs::SP);
+    assert_eq!(push.defs, Regs::SP);
+    assert_eq!(push.flags_used, Flags::all());
+    assert_eq!(push.mem_written, Some(MemRef::Stack));
+
+    let pop = Instruction::Pop { reg_pair: RegisterPair::PSW }.effects();
+    assert_eq!(pop.uses, Regs::SP);
+    assert_eq!(pop.defs, Regs::A | Regs::SP);
+    assert_eq!(pop.flags_defined, Flags::all());
+    assert_eq!(pop.mem_read, Some(MemRef::Stack));
+
+    // other pairs leave the flags alone
+    let pop = Instruction::Pop { reg_pair: RegisterPair::BC }.effects();
+    assert_eq!(pop.defs, Regs::B | Regs::C | Regs::SP);
+    assert_eq!(pop.flags_defined, Flags::empty());
+}
+
+#[test]
+fn dad_and_xthl() {
+    let dad = Instruction::Dad { reg_pair: RegisterPair::SP }.effects();
+    assert_eq!(dad.uses, HL | Regs::SP);
+    assert_eq!(dad.defs, HL);
+    assert_eq!(dad.flags_defined, Flags::CY);
+
+    let xthl = Instruction::Xthl.effects();
+    assert_eq!(xthl.uses, HL | Regs::SP);
+    assert_eq!(xthl.defs, HL);
+    assert_eq!(xthl.mem_read, Some(MemRef::Stack));
+    assert_eq!(xthl.mem_written, Some(MemRef::Stack));
+}
+
+#[test]
+fn rotates_and_daa() {
+    let ral = Instruction::Ral.effects();
+    assert_eq!((ral.uses, ral.defs), (Regs::A, Regs::A));
+    assert_eq!((ral.flags_used, ral.flags_defined), (Flags::CY, Flags::CY));
+    assert_eq!(Instruction::Rlc.effects().flags_used, Flags::empty());
+
+    let daa = Instruction::Daa.effects();
+    assert_eq!((daa.uses, daa.defs), (Regs::A, Regs::A));
+    assert_eq!(daa.flags_used, Flags::AC | Flags::CY);
+    assert_eq!(daa.flags_defined, Flags::S | Flags::Z | Flags::AC | Flags::P | Flags::CY);
+}
+
+#[test]
+fn undocumented() {
+    let dsub = Instruction::Dsub.effects();
+    assert_eq!(dsub.uses, HL | Regs::B | Regs::C);
+    assert_eq!(dsub.defs, HL);
+    assert!(dsub.flags_defined.contains(Flags::V | Flags::K | Flags::CY));
+
+    let ldhi = Instruction::Ldhi { imm: 4 }.effects();
+    assert_eq!((ldhi.uses, ldhi.defs), (HL, DE));
+    assert_eq!(ldhi.mem_read, None);
+
+    let shlx = Instruction::Shlx.effects();
+    assert_eq!(shlx.uses, HL | DE);
+    assert_eq!(shlx.defs, Regs::empty());
+    assert_eq!(shlx.mem_written, Some(MemRef::De));
+}
+
+#[test]
+fn conditional_flow() {
+    let z = Some(ConditionCodes::Z);
+    let jz = Instruction::Jmp { addr: 0x1337, condition: z }.effects();
+    assert_eq!(jz, Effects { flags_used: Flags::Z, ..Effects::default() });
+    assert_eq!(Instruction::Jmp { addr: 0x1337, condition: None }.effects(), Effects::default());
+
+    let cpo = Instruction::Call { addr: 0x1337, condition: Some(ConditionCodes::PO) }.effects();
+    assert_eq!(cpo.flags_used, Flags::P);
+    assert_eq!((cpo.uses, cpo.defs), (Regs::SP, Regs::SP));
+    assert_eq!(cpo.mem_written, Some(MemRef::Stack));
+
+    let rc = Instruction::Ret { condition: Some(ConditionCodes::C) }.effects();
+    assert_eq!(rc.flags_used, Flags::CY);
+    assert_eq!(rc.mem_read, Some(MemRef::Stack));
+    assert_eq!(Instruction::Ret { condition: None }.effects().flags_used, Flags::empty());
+
+    assert_eq!(Instruction::Jk { addr: 0x1337 }.effects().flags_used, Flags::K);
+}
@@ -0,0 +1,24 @@
+use ripntear::i8085::{Cpu, DecodeError, Instruction};
+use ripntear::memory::Region;
+use ripntear::MemoryImage;
+
+#[test]
+fn truncated_at_the_end() {
+    let mem = MemoryImage::rom(vec![0x00, 0xc3], 0);
+    assert_eq!(Instruction::decode_at(&mem, 0, Cpu::I8085), Ok((1, Instruction::Nop)));
+    let err = Instruction::decode_at(&mem, 1, Cpu::I8085).unwrap_err();
+    assert_eq!(err, DecodeError::Truncated { addr: 1, needed: 3, available: 1 });
+    assert_eq!(err.addr(), 1);
+    assert_eq!(err.to_string(), "truncated instruction at 0x0001: needs 3 bytes, 1 available");
+
+    assert_eq!(Instruction::decode(&[0x3e], 0x10, Cpu::I8085), Err(DecodeError::Truncated { addr: 0x10, needed: 2, available: 1 }));
+}
+
+#[test]
+fn unreadable() {
+    let mut mem = MemoryImage::rom(vec![0x00], 0);
+    mem.map(Region::mmio(0x10, 4)).unwrap();
+    // past the end, and where reading has side effects
+    assert_eq!(Instruction::decode_at(&mem, 1, Cpu::I8085), Err(DecodeError::Unreadable { addr: 1 }));
+    assert_eq!(Instruction::decode_at(&mem, 0x10, Cpu::I8085), Err(DecodeError::Unreadable { addr: 0x10 }));
+}
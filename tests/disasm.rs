@@ -0,0 +1,38 @@
+//! The disasm binary's options, run as a user would.
+
+use std::fs;
+use std::process::Command;
+
+/// Runs disasm on `image`, loaded at 0xfff0, with `args`.
+fn disasm(name: &str, image: &[u8], args: &[&str]) -> Result<String, String> {
+    let path = std::env::temp_dir().join(format!("ripntear-{}-{}.bin", name, std::process::id()));
+    fs::write(&path, image).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_disasm"))
+        .arg(format!("{}@fff0", path.display()))
+        .args(args)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    if out.status.success() {
+        Ok(String::from_utf8(out.stdout).unwrap())
+    } else {
+        Err(String::from_utf8(out.stderr).unwrap())
+    }
+}
+
+fn addresses(listing: &str) -> Vec<String> {
+    listing.lines().map(|line| line.split_whitespace().next().unwrap().to_string()).collect()
+}
+
+#[test]
+fn ranges() {
+    let nops = [0x00; 0x10];
+    let list = |args: &[&str]| disasm("ranges", &nops, &[&["--linear"], args].concat()).map(|out| addresses(&out));
+
+    assert_eq!(list(&["--start", "fffd", "--end", "ffff"]).unwrap(), ["fffd", "fffe"]);
+    // the end is exclusive, so the last byte needs 10000
+    assert_eq!(list(&["--start", "fffe", "--end", "10000"]).unwrap(), ["fffe", "ffff"]);
+    assert_eq!(list(&["--start", "0xfffe"]).unwrap(), ["fffe", "ffff"]);
+    assert!(list(&["--end", "10001"]).unwrap_err().contains("past 0x10000"));
+    assert!(list(&["--start", "10000"]).unwrap_err().contains("past 0xffff"));
+}
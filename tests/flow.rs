@@ -0,0 +1,27 @@
+use ripntear::i8085::{ConditionCodes, Flow, Instruction, Register};
+
+#[test]
+fn classification() {
+    use Instruction::*;
+    // instruction, flow, target, falls through
+    let table = [
+        (Mov { src: Register::A, dest: Register::B }, Flow::Fallthrough, None, true),
+        (Jmp { addr: 0x1337, condition: None }, Flow::Branch { target: 0x1337 }, Some(0x1337), false),
+        (Jmp { addr: 0x1337, condition: Some(ConditionCodes::NZ) }, Flow::ConditionalBranch { target: 0x1337 }, Some(0x1337), true),
+        (Jk { addr: 0x1337 }, Flow::ConditionalBranch { target: 0x1337 }, Some(0x1337), true),
+        (Jnk { addr: 0x1337 }, Flow::ConditionalBranch { target: 0x1337 }, Some(0x1337), true),
+        (Call { addr: 0x1337, condition: None }, Flow::Call { target: 0x1337 }, Some(0x1337), true),
+        (Call { addr: 0x1337, condition: Some(ConditionCodes::NZ) }, Flow::Call { target: 0x1337 }, Some(0x1337), true),
+        (Ret { condition: None }, Flow::Return, None, false),
+        (Ret { condition: Some(ConditionCodes::NZ) }, Flow::ConditionalReturn, None, true),
+        (Pchl, Flow::Indirect, None, false),
+        (Hlt, Flow::Halt, None, true),
+        (Rst { index: 7 }, Flow::Trap { vector: 0x38 }, Some(0x38), true),
+        (Rstv, Flow::Trap { vector: 0x40 }, Some(0x40), true),
+    ];
+    for (instr, flow, target, falls_through) in table {
+        assert_eq!(instr.flow(), flow, "{}", instr.raw_asm());
+        assert_eq!(flow.target(), target, "{}", instr.raw_asm());
+        assert_eq!(flow.falls_through(), falls_through, "{}", instr.raw_asm());
+    }
+}
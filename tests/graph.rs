@@ -0,0 +1,76 @@
+use ripntear::i8085::{Cfg, GraphFormat, GraphWriter, Tracer};
+use ripntear::MemoryImage;
+
+// 0000: call 0x0008
+// 0003: jmp 0x0003
+// 0006: db 0xff, 0xff
+// 0008: mvi a, 0x00
+// 000a: jz 0x000e
+// 000d: inr a
+// 000e: rnz
+// 000f: ret
+const PROGRAM: &[u8] = &[
+    0xcd, 0x08, 0x00, 0xc3, 0x03, 0x00, 0xff, 0xff,
+    0x3e, 0x00, 0xca, 0x0e, 0x00, 0x3c, 0xc0, 0xc9,
+];
+
+fn cfg() -> Cfg {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    Cfg::from_trace(&Tracer::new(&mem).trace(&[0]))
+}
+
+fn function(cfg: &Cfg, format: GraphFormat) -> String {
+    let mut out = Vec::new();
+    GraphWriter::new(cfg, format).function(&mut out, 0x08.into()).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn dot() {
+    let out = function(&cfg(), GraphFormat::Dot);
+    assert!(out.starts_with("digraph \"sub_0008\" {"));
+    assert!(out.contains("0008: mvi a, 0x0\\l000a: jz 0xe\\l"));
+    assert!(out.contains("b_0008 -> b_000e [color=green, label=\"true\"];"));
+    assert!(out.contains("b_0008 -> b_000d [color=red, label=\"false\"];"));
+    assert!(out.contains("b_000d -> b_000e;"));
+    assert!(out.trim_end().ends_with('}'));
+}
+
+#[test]
+fn mermaid() {
+    let out = function(&cfg(), GraphFormat::Mermaid);
+    assert!(out.contains("flowchart TD"));
+    assert!(out.contains("b_000f[\"000f: ret\"]"));
+    assert!(out.contains("b_0008 -->|true| b_000e"));
+    assert!(out.contains("b_0008 -->|false| b_000d"));
+    assert!(out.contains("stroke:green"));
+    assert!(out.contains("stroke:red"));
+}
+
+#[test]
+fn call_graph() {
+    let cfg = cfg();
+    let mut out = Vec::new();
+    GraphWriter::new(&cfg, GraphFormat::Dot).call_graph(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    // the call is from reset, which is a function of its own
+    assert!(out.contains("b_0000 -> b_0008;"));
+    assert!(!out.contains("(top level)"));
+
+    // without entry points, nothing holds the call
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let cfg = Cfg::build(trace.instructions(), &[]);
+    let mut out = Vec::new();
+    GraphWriter::new(&cfg, GraphFormat::Dot).call_graph(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("top [label=\"(top level)\\l\"];"));
+    assert!(out.contains("top -> b_0008;"));
+}
+
+#[test]
+fn formats() {
+    assert_eq!("dot".parse(), Ok(GraphFormat::Dot));
+    assert_eq!("Mermaid".parse(), Ok(GraphFormat::Mermaid));
+    assert!("svg".parse::<GraphFormat>().is_err());
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use serde_json::{json, Value};
+
+use ripntear::i8085::Tracer;
+use ripntear::{json, AddressWidth, MemoryImage, Printer, SymbolKind, Symbols};
+
+use common::two_banks;
+
+// 0000: call 0x0006
+// 0003: jmp 0x0003
+// 0006: lda 0xf800
+// 0009: out 0x10
+// 000b: ret
+// 000c: db "HELLO", 0
+const PROGRAM: &[u8] = &[
+    0xcd, 0x06, 0x00, 0xc3, 0x03, 0x00, 0x3a, 0x00, 0xf8, 0xd3, 0x10, 0xc9,
+    b'H', b'E', b'L', b'L', b'O', 0x00,
+];
+
+fn document() -> Value {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let xrefs = trace.xrefs().clone();
+    let mut symbols = Symbols::default();
+    symbols.insert(0xf800, "counter", SymbolKind::Data);
+    symbols.insert_port(0x10, "uart");
+    symbols.insert_comment(0x000b, "done");
+    let mut out = Vec::new();
+    Printer::new(trace.into_listing(), AddressWidth::Bits16)
+        .with_labels()
+        .with_symbols(symbols)
+        .with_xrefs(xrefs)
+        .with_memory(mem)
+        .print_json(&mut out, "8085")
+        .unwrap();
+    serde_json::from_slice(&out).unwrap()
+}
+
+#[test]
+fn header() {
+    let doc = document();
+    assert_eq!(doc["version"], json::VERSION);
+    assert_eq!(doc["cpu"], "8085");
+    assert!(doc["symbols"].as_array().unwrap().contains(&json!({
+        "address": 0xf800, "bank": null, "name": "counter", "kind": "data",
+    })));
+    assert_eq!(doc["ports"], json!([{"port": 0x10, "name": "uart"}]));
+    assert!(doc["xrefs"].as_array().unwrap().contains(&json!({"to": 6, "to_bank": null, "from": 0, "bank": null, "kind": "call"})));
+    assert!(doc["xrefs"].as_array().unwrap().contains(&json!({"to": 0x10, "to_bank": null, "from": 9, "bank": null, "kind": "out"})));
+}
+
+#[test]
+fn lines() {
+    let doc = document();
+    let segment = &doc["segments"][0];
+    assert_eq!(segment["start"], 0);
+    assert_eq!(segment["end"], PROGRAM.len());
+    let lines = segment["lines"].as_array().unwrap();
+
+    assert_eq!(lines[0], json!({
+        "address": 0, "bank": null, "bytes": [0xcd, 0x06, 0x00], "kind": "code",
+        "mnemonic": "call", "text": "call sub_0006",
+        "operands": [{"type": "code", "offset": 1, "register": null, "value": 6, "name": "sub_0006"}],
+        "flow": "call", "targets": [6], "labels": [], "comments": [],
+    }));
+    assert_eq!(lines[1]["flow"], "branch");
+    assert_eq!(lines[1]["labels"], json!(["loc_0003"]));
+    assert_eq!(lines[2]["operands"][0]["name"], "counter");
+    assert_eq!(lines[2]["comments"], json!(["XREF: 0000 (call)"]));
+    assert_eq!(lines[3]["operands"], json!([{"type": "port", "offset": 1, "register": null, "value": 0x10, "name": "uart"}]));
+    assert_eq!(lines[4]["flow"], "return");
+    assert_eq!(lines[4]["comments"], json!(["done"]));
+
+    let data = &lines[5];
+    assert_eq!(data["kind"], "data");
+    assert_eq!(data["text"], "db \"HELLO\", 0");
+    assert_eq!(data["flow"], Value::Null);
+    assert_eq!(data["bytes"].as_array().unwrap().len(), 6);
+}
+
+#[test]
+fn lines_one_per_line() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let mut out = Vec::new();
+    Printer::new(trace.into_listing(), AddressWidth::Bits16).with_labels().with_memory(mem).print_jsonl(&mut out, "8085").unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let mut lines: Vec<Value> = out.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let header = lines.remove(0);
+    assert_eq!(header, json!({"version": json::VERSION, "cpu": "8085", "banks": []}));
+    let document = document();
+    assert_eq!(lines.len(), document["segments"][0]["lines"].as_array().unwrap().len());
+    assert_eq!(lines[0]["text"], "call sub_0006");
+    assert_eq!(lines[5]["kind"], "data");
+}
+
+#[test]
+fn lines_keep_their_bank() {
+    // 0000: jmp 0x4000, with 0x4000 in either of two banks
+    let mut file = vec![0xff; 0xc000];
+    file[..3].copy_from_slice(&[0xc3, 0x00, 0x40]);
+    file[0x4000] = 0xc9;
+    file[0x8000] = 0x76;
+    let map = two_banks();
+    for section in Tracer::banked(&map, &file).trace(&[0]).into_sections() {
+        let mut out = Vec::new();
+        Printer::new(section.listing, AddressWidth::Bits16).print_jsonl_lines(&mut out).unwrap();
+        for line in String::from_utf8(out).unwrap().lines() {
+            let line: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(line["bank"], json!(section.bank));
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use ripntear::i8085::Tracer;
+use ripntear::{AddressWidth, Column, HexStyle, Layout, MemoryImage, Printer};
+
+// 0000: call 0x0006
+// 0003: jmp 0x0003
+// 0006: lxi h, 0xbeef
+// 0009: ret
+const PROGRAM: &[u8] = &[0xcd, 0x06, 0x00, 0xc3, 0x03, 0x00, 0x21, 0xef, 0xbe, 0xc9];
+
+fn render(layout: Layout) -> String {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let listing = Tracer::new(&mem).trace(&[0]).into_listing();
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16)
+        .with_labels()
+        .with_layout(layout)
+        .with_memory(mem)
+        .print(&mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn restyle() {
+    assert_eq!(HexStyle::Prefix.restyle("lxi hl, 0xbeef", true), "lxi hl, 0xBEEF");
+    assert_eq!(HexStyle::Dollar.restyle("mvi a, 0x1f", false), "mvi a, $1f");
+    assert_eq!(HexStyle::Suffix.restyle("db 0x12, 0xff", false), "db 12h, 0ffh");
+    // names and strings are left alone
+    assert_eq!(HexStyle::Suffix.restyle("jmp sub_0x12", false), "jmp sub_0x12");
+    assert_eq!(HexStyle::Suffix.restyle("db \"0x12\", 0x0", false), "db \"0x12\", 0h");
+    assert_eq!("h".parse(), Ok(HexStyle::Suffix));
+    assert!("%".parse::<HexStyle>().is_err());
+}
+
+#[test]
+fn listing() {
+    let out = render(Layout { hex: HexStyle::Suffix, uppercase: true, ..Layout::listing() });
+    assert_eq!(out, "\
+0000    CD 06 00          call   sub_0006
+0003                  loc_0003:
+0003    C3 03 00          jmp    loc_0003
+0006                  sub_0006:
+0006    21 EF BE          lxi    hl, 0BEEFh
+0009    C9                ret
+");
+}
+
+#[test]
+fn inline_labels() {
+    let out = render(Layout::source());
+    assert_eq!(out, concat!(
+        "                    call sub_0006\n",
+        "loc_0003:           jmp loc_0003\n",
+        "sub_0006:           lxi hl, 0xbeef\n",
+        "                    ret\n",
+    ));
+}
+
+#[test]
+fn columns() {
+    let columns = vec![Column::Bytes, Column::Address, Column::Mnemonic];
+    let out = render(Layout { columns, bytes: 2, ..Layout::default() });
+    assert!(out.starts_with("cd 06 +    0000        call\n"));
+    assert_eq!("operands".parse(), Ok(Column::Operands));
+}
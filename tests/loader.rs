@@ -0,0 +1,96 @@
+use ripntear::loader::{Format, Image, LoadErrorKind, Segment};
+
+#[test]
+fn intel_hex() {
+    let text = "\
+:03000000C30080BA
+:020000040001F9
+:010010007679
+:040000050000800077
+:00000001FF
+";
+    let image = Image::from_ihex(text).unwrap();
+    assert_eq!(image.segments(), [
+        Segment { addr: 0x0000, data: vec![0xc3, 0x00, 0x80] },
+        Segment { addr: 0x10010, data: vec![0x76] },
+    ]);
+    assert_eq!(image.entry, Some(0x8000));
+    assert_eq!(image.read(0x0001), Some(0x00));
+    assert_eq!(image.read(0x0003), None);
+}
+
+#[test]
+fn intel_hex_errors() {
+    let err = Image::from_ihex(":03000000C30080BA\n:03000000C30080BB\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(err.kind, LoadErrorKind::Checksum { expected: 0xba, found: 0xbb });
+
+    assert!(Image::from_ihex("03000000C30080BA").is_err());
+    assert!(Image::from_ihex(":03000000C300BA").is_err());
+}
+
+#[test]
+fn s_records() {
+    let text = "\
+S00600004844521B
+S10580003E013B
+S10480047601
+S90380007C
+";
+    let image = Image::from_srec(text).unwrap();
+    assert_eq!(image.segments(), [
+        Segment { addr: 0x8000, data: vec![0x3e, 0x01] },
+        Segment { addr: 0x8004, data: vec![0x76] },
+    ]);
+    assert_eq!(image.entry, Some(0x8000));
+
+    let err = Image::from_srec("S10580003E013C\n").unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(matches!(err.kind, LoadErrorKind::Checksum { .. }));
+}
+
+#[test]
+fn segments_merge_and_flatten() {
+    let mut image = Image::raw(vec![1, 2, 3], 0x100);
+    image.insert(0x103, &[4]);
+    image.insert(0x101, &[0x22]);
+    image.insert(0x108, &[8]);
+    assert_eq!(image.segments().len(), 2);
+    assert_eq!(image.bounds(), Some((0x100, 0x109)));
+
+    let (base, flat) = image.flatten(0xff);
+    assert_eq!(base, 0x100);
+    assert_eq!(flat, [1, 0x22, 3, 4, 0xff, 0xff, 0xff, 0xff, 8]);
+}
+
+#[test]
+fn inserts_bridge_and_overlap() {
+    let mut image = Image::default();
+    image.insert(0x10, &[1, 2]);
+    image.insert(0x20, &[5]);
+    image.insert(0x08, &[9]);
+    assert_eq!(image.segments().len(), 3);
+
+    // starting before one segment and running up to the next joins them
+    image.insert(0x0f, &[3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3]);
+    assert_eq!(image.segments().len(), 2);
+    assert_eq!(image.segments()[0].addr, 0x08);
+    let mut joined = vec![3; 17];
+    joined.push(5);
+    assert_eq!(image.segments()[1], Segment { addr: 0x0f, data: joined });
+
+    // and large images built a record at a time stay as one segment
+    for addr in (0x1000..0x11000).step_by(16) {
+        image.insert(addr, &[0x55; 16]);
+    }
+    assert_eq!(image.segments().len(), 3);
+    assert_eq!(image.bounds(), Some((0x08, 0x11000)));
+}
+
+#[test]
+fn format_from_extension() {
+    assert_eq!(Format::from_path("rom.HEX"), Format::IntelHex);
+    assert_eq!(Format::from_path("rom.s19"), Format::Srec);
+    assert_eq!(Format::from_path("rom.bin"), Format::Binary);
+    assert_eq!(Format::from_path("rom"), Format::Binary);
+}
@@ -0,0 +1,37 @@
+use ripntear::i8085::{Cpu, DecodeError, Instruction, Register};
+use ripntear::{MemoryImage, Region, RegionKind};
+
+#[test]
+fn regions() {
+    let mut mem = MemoryImage::rom(vec![0x3e, 0x01], 0x0000);
+    mem.map(Region::mmio(0x4000, 0x10)).unwrap();
+    mem.map(Region::ram(0x8000, 0x800)).unwrap();
+    assert_eq!(mem.map(Region::ram(0x8400, 0x800)).unwrap_err().addr, 0x8400);
+
+    assert_eq!(mem.kind(0x0001), RegionKind::Rom);
+    assert_eq!(mem.kind(0x4008), RegionKind::Mmio);
+    assert_eq!(mem.kind(0x8000), RegionKind::Ram);
+    assert_eq!(mem.kind(0x2000), RegionKind::Unmapped);
+
+    assert_eq!(mem.read(0x0001), Some(0x01));
+    // MMIO isn't readable, and RAM's contents are unknown
+    assert_eq!(mem.read(0x4000), None);
+    assert_eq!(mem.read(0x8000), None);
+    assert_eq!(mem.fetch(0x0000, 3), [0x3e, 0x01]);
+}
+
+#[test]
+fn decode_at() {
+    let mut mem = MemoryImage::rom(vec![0x3e, 0x01, 0xc3], 0x0000);
+    mem.map(Region::rom(0x0003, vec![0x00, 0x10, 0x3e])).unwrap();
+    mem.map(Region::ram(0x0006, 0x10)).unwrap();
+    assert_eq!(mem.regions().len(), 3);
+
+    assert_eq!(Instruction::decode_at(&mem, 0x0000, Cpu::I8085), Ok((2, Instruction::Mvi { reg: Register::A, value: 1 })));
+    // from one region straight on into the next
+    assert_eq!(Instruction::decode_at(&mem, 0x0002, Cpu::I8085), Ok((3, Instruction::Jmp { addr: 0x1000, condition: None })));
+    assert_eq!(Instruction::decode_at(&mem, 0x0004, Cpu::I8085Undoc), Ok((1, Instruction::Arhl)));
+    // but not into RAM, whose contents aren't known
+    assert_eq!(Instruction::decode_at(&mem, 0x0005, Cpu::I8085), Err(DecodeError::Truncated { addr: 0x0005, needed: 2, available: 1 }));
+    assert_eq!(Instruction::decode_at(&mem, 0x0006, Cpu::I8085), Err(DecodeError::Unreadable { addr: 0x0006 }));
+}
@@ -0,0 +1,54 @@
+use std::fs;
+
+use ripntear::i8085::{Cpu, Instruction, Operand, Register, RegisterPair};
+use ripntear::MemoryImage;
+
+fn decode(bytes: &[u8]) -> Instruction {
+    let mem = MemoryImage::rom(bytes.to_vec(), 0);
+    Instruction::decode_at(&mem, 0, Cpu::I8085Undoc).unwrap().1
+}
+
+#[test]
+fn typed() {
+    let operands = |bytes: &[u8]| decode(bytes).operands().collect::<Vec<_>>();
+    assert_eq!(operands(&[0x78]), [(0, Operand::Register(Register::A)), (0, Operand::Register(Register::B))]);
+    assert_eq!(operands(&[0x3e, 0x12]), [(0, Operand::Register(Register::A)), (1, Operand::Imm8(0x12))]);
+    assert_eq!(operands(&[0x21, 0x37, 0x13]), [(0, Operand::RegisterPair(RegisterPair::HL)), (1, Operand::Imm16(0x1337))]);
+    assert_eq!(operands(&[0xc2, 0x37, 0x13]), [(1, Operand::Code(0x1337))]);
+    assert_eq!(operands(&[0x32, 0x00, 0xf8]), [(1, Operand::Data(0xf800))]);
+    assert_eq!(operands(&[0xd3, 0x10]), [(1, Operand::Port(0x10))]);
+    assert_eq!(operands(&[0xef]), [(0, Operand::Vector(5))]);
+    assert_eq!(operands(&[0xc9]), []);
+}
+
+#[test]
+fn addresses() {
+    assert_eq!(Operand::Vector(5).address(), Some(0x28));
+    assert_eq!(Operand::Imm16(0x1337).address(), Some(0x1337));
+    assert_eq!(Operand::Imm8(0x12).address(), None);
+    assert_eq!(Operand::Port(0x10).value(), Some(0x10));
+    assert_eq!(Operand::Register(Register::Mem).value(), None);
+}
+
+#[test]
+fn offsets_match_the_encoding() {
+    let bin = fs::read("testdata/allinstructions.bin").unwrap();
+    let mem = MemoryImage::rom(bin.clone(), 0);
+    let mut i = 0;
+    while i < bin.len() {
+        let (len, instr) = Instruction::decode_at(&mem, i, Cpu::I8085Undoc).unwrap();
+        let bytes = &bin[i..i + len];
+        for (offset, operand) in instr.operands() {
+            let value = operand.value();
+            match operand {
+                Operand::Register(_) | Operand::RegisterPair(_) | Operand::Vector(_) => assert_eq!(offset, 0),
+                Operand::Imm8(_) | Operand::Port(_) => assert_eq!(value, Some(bytes[offset] as u16)),
+                Operand::Imm16(_) | Operand::Code(_) | Operand::Data(_) => {
+                    assert_eq!(value, Some(u16::from_le_bytes([bytes[offset], bytes[offset + 1]])));
+                    assert_eq!(offset + 2, len);
+                }
+            }
+        }
+        i += len;
+    }
+}
@@ -0,0 +1,63 @@
+use ripntear::i8085::{Cpu, Tracer};
+use ripntear::{AddressWidth, MemoryImage, Printer};
+
+fn render(printer: Printer<ripntear::Entry<ripntear::i8085::Instruction>>) -> String {
+    let mut out = Vec::new();
+    printer.print(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+// 0000: call 0x0006
+// 0003: jmp 0x0003
+// 0006: rst 1
+// 0007: ret
+// 0008: ret
+const PROGRAM: &[u8] = &[0xcd, 0x06, 0x00, 0xc3, 0x03, 0x00, 0xcf, 0xc9, 0xc9];
+
+#[test]
+fn generated_labels() {
+    let listing = Tracer::new(&MemoryImage::rom(PROGRAM.to_vec(), 0)).trace(&[0]).into_listing();
+    let out = render(Printer::new(listing, AddressWidth::Bits16).with_labels());
+    assert_eq!(out, "\
+0000        call sub_0006
+0003    loc_0003:
+0003        jmp loc_0003
+0006    sub_0006:
+0006        rst 0x1
+0007        ret
+0008    rst_vec_1:
+0008        ret
+");
+}
+
+#[test]
+fn user_names_override() {
+    let listing = Tracer::new(&MemoryImage::rom(PROGRAM.to_vec(), 0)).trace(&[0]).into_listing();
+    let out = render(Printer::new(listing, AddressWidth::Bits16).with_labels().with_name(0x0006, "init"));
+    assert!(out.contains("0006    init:\n"));
+    assert!(out.contains("call init\n"));
+}
+
+#[test]
+fn xref_comments() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let xrefs = trace.xrefs().clone();
+    let out = render(Printer::new(trace.into_listing(), AddressWidth::Bits16).with_labels().with_xrefs(xrefs));
+    assert!(out.contains("0003    loc_0003:\n0003        ; XREF: loc_0003 (jump)\n"));
+    assert!(out.contains("0006        ; XREF: 0000 (call)\n"));
+    assert!(out.contains("0008        ; XREF: sub_0006 (call)\n"));
+}
+
+#[test]
+fn cycles_for_the_cpu() {
+    // mov a, a; nop; rnz; hlt
+    let program = vec![0x7f, 0x00, 0xc0, 0x76];
+    let cycles = |cpu| {
+        let listing = Tracer::new(&MemoryImage::rom(program.clone(), 0)).with_cpu(cpu).trace(&[0]).into_listing();
+        let out = render(Printer::new(listing, AddressWidth::Bits16).with_cycles(cpu));
+        out.lines().map(|line| line.split_whitespace().nth(1).unwrap().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(cycles(Cpu::I8080), ["5", "4", "5/11", "7"]);
+    assert_eq!(cycles(Cpu::I8085), ["4", "4", "6/12", "5"]);
+}
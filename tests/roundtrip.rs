@@ -0,0 +1,31 @@
+use ripntear::i8085::Instruction;
+
+/// Every opcode, followed by enough operand bytes for the longest instruction.
+fn every_opcode() -> impl Iterator<Item = [u8; 3]> {
+    (0..=0xffu8).map(|opcode| [opcode, 0x37, 0x13])
+}
+
+#[test]
+fn decode_encode() {
+    for buf in every_opcode() {
+        let (len, instr) = Instruction::decode_one(&buf, 0).unwrap();
+        assert_eq!(instr.encode().unwrap(), &buf[..len], "{}", instr.raw_asm());
+    }
+}
+
+#[test]
+fn print_parse() {
+    for buf in every_opcode() {
+        let (_, instr) = Instruction::decode_one(&buf, 0).unwrap();
+        let text = instr.raw_asm();
+        assert_eq!(text.parse::<Instruction>().unwrap(), instr, "{}", text);
+    }
+}
+
+#[test]
+fn parse_intel_names() {
+    assert_eq!("LXI H, 1337h".parse::<Instruction>().unwrap().encode().unwrap(), [0x21, 0x37, 0x13]);
+    assert_eq!("push psw ; save flags".parse::<Instruction>().unwrap().encode().unwrap(), [0xf5]);
+    assert!("mvi a, 0x100".parse::<Instruction>().is_err());
+    assert!("frob a".parse::<Instruction>().is_err());
+}
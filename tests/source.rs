@@ -0,0 +1,223 @@
+//! Source output, checked by assembling it back. There's no AS here, so this assembles the
+//! subset `Printer::print_source` writes.
+
+use std::collections::HashMap;
+use std::fs;
+
+use ripntear::i8085::{Cpu, Instruction, Tracer};
+use ripntear::{AddressWidth, Entry, MemoryImage, Printer};
+
+/// Splits `text` at commas outside quotes.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = vec![String::new()];
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => {
+                operands.push(String::new());
+                continue;
+            }
+            None => {}
+        }
+        operands.last_mut().unwrap().push(c);
+    }
+    operands.iter().map(|op| op.trim().to_string()).filter(|op| !op.is_empty()).collect()
+}
+
+/// `text` up to any comment.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ';' => return &text[..i],
+            None => {}
+        }
+    }
+    text
+}
+
+fn number(text: &str, symbols: &HashMap<String, usize>) -> usize {
+    if let Some(ch) = text.strip_suffix("'|80h").and_then(|c| c.strip_prefix('\'')) {
+        return ch.as_bytes()[0] as usize | 0x80;
+    }
+    let text = text.to_lowercase();
+    if let Some(&value) = symbols.get(&text) {
+        return value;
+    }
+    match text.strip_suffix('h') {
+        Some(hex) => usize::from_str_radix(hex, 16).unwrap(),
+        None => text.parse().unwrap_or_else(|_| panic!("bad number {:?}", text)),
+    }
+}
+
+fn data(operand: &str, symbols: &HashMap<String, usize>) -> Vec<u8> {
+    if let Some(text) = operand.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return text.as_bytes().to_vec();
+    }
+    if let Some((count, value)) = operand.split_once(" dup ") {
+        let value = value.trim_start_matches('(').trim_end_matches(')');
+        return vec![number(value, symbols) as u8; number(count, symbols)];
+    }
+    vec![number(operand, symbols) as u8]
+}
+
+/// Whether `operand` is a name to look up, rather than a register or a number.
+fn is_name(operand: &str) -> bool {
+    const REGISTERS: [&str; 10] = ["a", "b", "c", "d", "e", "h", "l", "m", "sp", "psw"];
+    operand.starts_with(|c: char| c.is_ascii_alphabetic()) && !REGISTERS.contains(&operand.to_lowercase().as_str())
+}
+
+/// Assembles `source` into `(address, bytes)` for each line. Names not defined yet count
+/// as 0, so the first of two passes finds where every label is.
+fn pass(source: &str, symbols: &mut HashMap<String, usize>) -> Vec<(usize, Vec<u8>)> {
+    let mut out = Vec::new();
+    let mut pc = 0;
+    for line in source.lines() {
+        let line = strip_comment(line).trim_end();
+        if let Some(label) = line.strip_suffix(':') {
+            symbols.insert(label.to_lowercase(), pc);
+            continue;
+        }
+        if let Some((name, value)) = line.split_once("\tEQU ") {
+            let value = number(value, symbols);
+            symbols.insert(name.to_lowercase(), value);
+            continue;
+        }
+        let line = line.trim();
+        let (mnemonic, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let operands = split_operands(rest);
+        let bytes = match mnemonic.to_lowercase().as_str() {
+            "" | "cpu" => continue,
+            "org" => {
+                pc = number(&operands[0], symbols);
+                continue;
+            }
+            "db" => operands.iter().flat_map(|op| data(op, symbols)).collect(),
+            "dw" => operands.iter().flat_map(|op| (number(op, symbols) as u16).to_le_bytes()).collect(),
+            _ => {
+                let operands: Vec<String> = operands.iter()
+                    .map(|op| match symbols.get(&op.to_lowercase()) {
+                        Some(value) => format!("{:#x}", value),
+                        None if is_name(op) => "0".to_string(),
+                        None => op.clone(),
+                    })
+                    .collect();
+                let text = format!("{} {}", mnemonic, operands.join(", "));
+                let instr: Instruction = text.parse().unwrap_or_else(|e| panic!("{}: {}", text, e));
+                instr.encode().unwrap()
+            }
+        };
+        let len = bytes.len();
+        out.push((pc, bytes));
+        pc += len;
+    }
+    out
+}
+
+fn assemble(source: &str) -> Vec<u8> {
+    let mut symbols = HashMap::new();
+    pass(source, &mut symbols);
+    let mut image = Vec::new();
+    for (addr, bytes) in pass(source, &mut symbols) {
+        if image.len() < addr + bytes.len() {
+            image.resize(addr + bytes.len(), 0);
+        }
+        image[addr..addr + bytes.len()].copy_from_slice(&bytes);
+    }
+    image
+}
+
+fn source(program: &[u8], cpu: Cpu, entries: &[u16]) -> String {
+    let mem = MemoryImage::rom(program.to_vec(), 0);
+    let listing = Tracer::new(&mem).with_cpu(cpu).trace(entries).into_listing();
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16)
+        .with_labels()
+        .with_memory(mem)
+        .print_source(&mut out, &cpu.to_string())
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn reassembles_every_instruction() {
+    let bin = fs::read("testdata/allinstructions.bin").unwrap();
+    let mem = MemoryImage::rom(bin.clone(), 0);
+    let mut listing = Vec::new();
+    let mut i = 0;
+    while i < bin.len() {
+        let (len, instr) = Instruction::decode_at(&mem, i, Cpu::I8085Undoc).unwrap();
+        listing.push((i, Entry::Code(instr)));
+        i += len;
+    }
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16).with_labels().print_source(&mut out, "8085undoc").unwrap();
+    let source = String::from_utf8(out).unwrap();
+
+    assert!(source.starts_with("\tCPU 8085UNDOC\n\tORG 0h\n"));
+    assert!(source.contains("\tlxi h, 1137h\n"));
+    assert!(source.contains("\tpush psw\n"));
+    assert!(source.contains("\tjx5 1337h\n"));
+    assert_eq!(assemble(&source), bin);
+
+    // the hand-written source, but for its labels
+    let expected = fs::read_to_string("testdata/allinstructions.asm").unwrap();
+    let unlabelled = |text: &str| text.lines().filter(|line| !line.ends_with(':')).map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(unlabelled(&source), unlabelled(&expected));
+}
+
+#[test]
+fn reassembles_code_and_data() {
+    // 0000: lxi d, 0x0010
+    // 0003: call 0x000a
+    // 0006: jmp 0x0006
+    // 0009: db 0x00
+    // 000a: lda 0xf800
+    // 000d: ret
+    // 000e: strings and padding
+    let mut program = vec![
+        0x11, 0x10, 0x00, 0xcd, 0x0a, 0x00, 0xc3, 0x06, 0x00, 0x00,
+        0x3a, 0x00, 0xf8, 0xc9, 0x01, 0x02,
+    ];
+    program.extend(b"HELLO;\0WORL\xc4");
+    program.extend([0xff; 12]);
+    program.extend([0x34, 0x12]);
+
+    let source = source(&program, Cpu::I8085Undoc, &[0]);
+    assert!(source.contains("sub_000a:\n\tlda 0f800h\n"));
+    assert!(source.contains("\tdb \"HELLO;\", 0\n"));
+    assert!(source.contains("\tdb \"WORL\", 'D'|80h\n"));
+    assert!(source.contains("\tdb 12 dup (0ffh)\n"));
+    assert_eq!(assemble(&source), program);
+}
+
+#[test]
+fn aliases_stay_as_bytes() {
+    // on the 8080, 0x08 is an alias of nop and 0xcb of jmp
+    let program = [0x08, 0xcb, 0x00, 0x00];
+    let source = source(&program, Cpu::I8080, &[0]);
+    assert!(source.starts_with("\tCPU 8080\n"));
+    assert!(source.contains("\tdb 08h\t; nop\n"));
+    assert_eq!(assemble(&source), program);
+}
+
+#[test]
+fn names_off_the_listing() {
+    let mem = MemoryImage::rom(vec![0x3a, 0x00, 0xf8, 0xd3, 0x10, 0x76], 0);
+    let listing = Tracer::new(&mem).trace(&[0]).into_listing();
+    let mut symbols = ripntear::Symbols::default();
+    symbols.insert(0xf800, "counter", ripntear::SymbolKind::Data);
+    symbols.insert_port(0x10, "bank");
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16).with_symbols(symbols).print_source(&mut out, "8085").unwrap();
+    let source = String::from_utf8(out).unwrap();
+    assert!(source.contains("counter\tEQU 0f800h\nbank\tEQU 10h\n"));
+    assert!(source.contains("\tlda counter\n\tout bank\n"));
+    assert_eq!(assemble(&source), [0x3a, 0x00, 0xf8, 0xd3, 0x10, 0x76]);
+}
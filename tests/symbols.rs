@@ -0,0 +1,83 @@
+use ripntear::{BankedAddress, SymbolKind, Symbols};
+
+#[test]
+fn round_trip() {
+    let text = "\
+# comment
+code    0x0000  reset
+data    0xf800  tick_count
+port    0x10    uart_data
+comment 0x0042  waits for the UART to drain
+";
+    let symbols = Symbols::parse(text).unwrap();
+    assert_eq!(symbols.name(0x0000), Some("reset"));
+    assert_eq!(symbols.symbol(0xf800).unwrap().kind, SymbolKind::Data);
+    assert_eq!(symbols.port(0x10), Some("uart_data"));
+    assert_eq!(symbols.comment(0x42), Some("waits for the UART to drain"));
+
+    let mut out = Vec::new();
+    symbols.write(&mut out).unwrap();
+    let reparsed = Symbols::parse(&String::from_utf8(out).unwrap()).unwrap();
+    assert_eq!(reparsed.iter().collect::<Vec<_>>(), symbols.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn parse_errors() {
+    let err = Symbols::parse("code 0x0000 reset\nlabel 0x10 foo\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert!(Symbols::parse("code zzz foo").is_err());
+    assert!(Symbols::parse("code 0x10").is_err());
+}
+
+#[test]
+fn as_listing() {
+    let text = "\
+ AS V1.42 Beta [Bld 212] - Source File allinstructions.asm - Page 2
+
+  Symbol Table (* = unused):
+  --------------------------
+
+*ARCHITECTURE :                                        \"x86_64-unknown-linux\" - |
+ FLOW :                           0 C | *MEM :                          D2 C |
+ COUNTER :                     F800 D | *CONSTPI :        3.141592653589793239 - |
+";
+    let symbols = Symbols::from_as_listing(text);
+    assert_eq!(symbols.name(0x0000), Some("FLOW"));
+    assert_eq!(symbols.name(0x00d2), Some("MEM"));
+    assert_eq!(symbols.symbol(0xf800).unwrap().kind, SymbolKind::Data);
+    assert_eq!(symbols.iter().count(), 3);
+}
+
+#[test]
+fn mame_comments() {
+    let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<mamecommentfile version="1">
+    <system name="pc8300">
+        <cpu tag=":maincpu">
+            <comment address="4919" color="16711680" crc="abcd">copy &lt;hl&gt; &amp; go</comment>
+        </cpu>
+    </system>
+</mamecommentfile>
+"#;
+    let symbols = Symbols::from_mame_comments(text);
+    assert_eq!(symbols.comment(0x1337), Some("copy <hl> & go"));
+}
+
+#[test]
+fn banked_symbols() {
+    let symbols = Symbols::parse("code 0x4000 common\ncode 0x01:0x4000 banked\ncomment 1:0x4003 in bank 1\n").unwrap();
+    let banked = BankedAddress::new(Some(1), 0x4000);
+    assert_eq!(symbols.name(banked), Some("banked"));
+    assert_eq!(symbols.name(BankedAddress::new(Some(2), 0x4000)), Some("common"));
+    assert_eq!(symbols.name(0x4000), Some("common"));
+    assert_eq!(symbols.in_bank(Some(1)).name(0x4000), Some("banked"));
+    assert_eq!(symbols.in_bank(Some(1)).comment(0x4003), Some("in bank 1"));
+    assert_eq!(symbols.in_bank(None).comment(0x4003), None);
+
+    let mut out = Vec::new();
+    symbols.write(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("code    0x01:0x4000  banked\n"));
+    assert_eq!(Symbols::parse(&out).unwrap().name(banked), Some("banked"));
+    assert!(Symbols::parse("port 1:0x10 banked").is_err());
+}
@@ -0,0 +1,98 @@
+use std::fs;
+
+use ripntear::i8085::{Cpu, Instruction, Tracer};
+use ripntear::{AddressWidth, Layout, MemoryImage, Printer, SymbolKind, Symbols, Syntax};
+
+fn asm(bytes: &[u8], syntax: Syntax) -> String {
+    let mem = MemoryImage::rom(bytes.to_vec(), 0);
+    let (_, instr) = Instruction::decode_at(&mem, 0, Cpu::I8085Undoc).unwrap();
+    instr.asm_in(syntax, &Symbols::default())
+}
+
+#[test]
+fn intel() {
+    assert_eq!(asm(&[0x21, 0x37, 0x13], Syntax::Intel), "lxi h, 0x1337");
+    assert_eq!(asm(&[0xf5], Syntax::Intel), "push psw");
+    assert_eq!(asm(&[0xfd, 0x37, 0x13], Syntax::Intel), "jx5 0x1337");
+    // raw keeps the full pair names, as before
+    assert_eq!(asm(&[0x21, 0x37, 0x13], Syntax::Raw), "lxi hl, 0x1337");
+    assert_eq!(asm(&[0xfd, 0x37, 0x13], Syntax::Raw), "jk 0x1337");
+
+    let (_, instr) = Instruction::decode(&[0x21, 0x37, 0x13], 0, Cpu::I8085).unwrap();
+    assert_eq!(instr.intel_asm_with(&Symbols::default()), "lxi h, 0x1337");
+}
+
+#[test]
+fn zilog() {
+    assert_eq!(asm(&[0x7e], Syntax::Zilog), "ld a,(hl)");
+    assert_eq!(asm(&[0xc2, 0x37, 0x13], Syntax::Zilog), "jp nz,0x1337");
+    assert_eq!(asm(&[0x19], Syntax::Zilog), "add hl,de");
+    assert_eq!(asm(&[0x3a, 0x00, 0xf8], Syntax::Zilog), "ld a,(0xf800)");
+    assert_eq!(asm(&[0x1a], Syntax::Zilog), "ld a,(de)");
+    assert_eq!(asm(&[0xd3, 0x10], Syntax::Zilog), "out (0x10),a");
+    assert_eq!(asm(&[0xf1], Syntax::Zilog), "pop af");
+    assert_eq!(asm(&[0xef], Syntax::Zilog), "rst 0x28");
+    assert_eq!(asm(&[0xe9], Syntax::Zilog), "jp (hl)");
+    assert_eq!(asm(&[0xd8], Syntax::Zilog), "ret c");
+    assert_eq!(asm(&[0x76], Syntax::Zilog), "halt");
+}
+
+#[test]
+fn tdl() {
+    assert_eq!(asm(&[0x7e], Syntax::Tdl), "MOV A,M");
+    assert_eq!(asm(&[0x21, 0x37, 0x13], Syntax::Tdl), "LXI H,0x1337");
+    assert_eq!(asm(&[0xc2, 0x37, 0x13], Syntax::Tdl), "JNZ 0x1337");
+}
+
+#[test]
+fn names_stay_as_they_are() {
+    let mut symbols = Symbols::default();
+    symbols.insert(0xf800, "Counter", SymbolKind::Data);
+    symbols.insert_port(0x10, "bank");
+    let mem = MemoryImage::rom(vec![0x3a, 0x00, 0xf8, 0xdb, 0x10], 0);
+    let (_, lda) = Instruction::decode_at(&mem, 0, Cpu::I8085).unwrap();
+    let (_, input) = Instruction::decode_at(&mem, 3, Cpu::I8085).unwrap();
+    assert_eq!(lda.asm_in(Syntax::Tdl, &symbols), "LDA Counter");
+    assert_eq!(lda.asm_in(Syntax::Zilog, &symbols), "ld a,(Counter)");
+    assert_eq!(input.asm_in(Syntax::Zilog, &symbols), "in a,(bank)");
+}
+
+#[test]
+fn intel_parses_back() {
+    let bin = fs::read("testdata/allinstructions.bin").unwrap();
+    let mem = MemoryImage::rom(bin.clone(), 0);
+    let mut i = 0;
+    while i < bin.len() {
+        let (len, instr) = Instruction::decode_at(&mem, i, Cpu::I8085Undoc).unwrap();
+        // every instruction has a rendering in each syntax
+        assert!(!instr.asm_in(Syntax::Zilog, &Symbols::default()).is_empty());
+        let text = instr.asm_in(Syntax::Intel, &Symbols::default());
+        assert_eq!(text.parse::<Instruction>(), Ok(instr), "{}", text);
+        i += len;
+    }
+}
+
+#[test]
+fn printer() {
+    // 0000: call 0x0004
+    // 0003: hlt
+    // 0004: ldax d
+    // 0005: ret
+    let mem = MemoryImage::rom(vec![0xcd, 0x04, 0x00, 0x76, 0x1a, 0xc9], 0);
+    let listing = Tracer::new(&mem).trace(&[0]).into_listing();
+    let mut out = Vec::new();
+    Printer::new(listing, AddressWidth::Bits16)
+        .with_labels()
+        .with_layout(Layout::source())
+        .with_syntax(Syntax::Zilog)
+        .print(&mut out)
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        "                    call sub_0004\n",
+        "                    halt\n",
+        "sub_0004:           ld a,(de)\n",
+        "                    ret\n",
+    ));
+    assert_eq!("z80".parse(), Ok(Syntax::Zilog));
+    assert!("motorola".parse::<Syntax>().is_err());
+}
@@ -0,0 +1,39 @@
+use ripntear::i8085::timing::{counted_loop, straight_line};
+use ripntear::i8085::{ConditionCodes, Cpu, Cycles, Instruction, Register, RegisterPair};
+
+fn both(instr: &Instruction) -> (Cycles, Cycles) {
+    (instr.cycles(Cpu::I8080), instr.cycles(Cpu::I8085))
+}
+
+#[test]
+fn i8080_and_i8085() {
+    let nz = Some(ConditionCodes::NZ);
+    assert_eq!(both(&Instruction::Mov { src: Register::A, dest: Register::B }), (Cycles::Fixed(5), Cycles::Fixed(4)));
+    assert_eq!(both(&Instruction::Inx { reg_pair: RegisterPair::HL }), (Cycles::Fixed(5), Cycles::Fixed(6)));
+    assert_eq!(
+        both(&Instruction::Call { addr: 0x1337, condition: nz }),
+        (Cycles::Conditional { not_taken: 11, taken: 17 }, Cycles::Conditional { not_taken: 9, taken: 18 }),
+    );
+    assert_eq!(
+        both(&Instruction::Ret { condition: nz }),
+        (Cycles::Conditional { not_taken: 5, taken: 11 }, Cycles::Conditional { not_taken: 6, taken: 12 }),
+    );
+    assert_eq!(Instruction::Ret { condition: nz }.cycles(Cpu::I8085).to_string(), "6/12");
+}
+
+#[test]
+fn loops() {
+    // mvi b, 10
+    // loop: dcr b
+    //       jnz loop
+    let setup = [Instruction::Mvi { reg: Register::B, value: 10 }];
+    let body = [
+        Instruction::Dcr { reg: Register::B },
+        Instruction::Jmp { addr: 0x0002, condition: Some(ConditionCodes::NZ) },
+    ];
+    assert_eq!(straight_line(&setup, Cpu::I8085), 7);
+    // 10 passes of dcr, the jnz taken 9 times and falling through once
+    assert_eq!(counted_loop(&body, 10, Cpu::I8085), 10 * 4 + 9 * 10 + 7);
+    assert_eq!(counted_loop(&body, 10, Cpu::I8080), 10 * 5 + 9 * 10 + 10);
+    assert_eq!(counted_loop(&body, 0, Cpu::I8085), 0);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use ripntear::i8085::trace::{ByteKind, ENTRY_POINTS};
+use ripntear::i8085::trace::Section;
+use ripntear::printer::generate_labels;
+use ripntear::i8085::{DecodeError, Tracer};
+use ripntear::{BankId, BankedAddress, Entry, MemoryImage, Region};
+
+use common::{two_banks, A, B, BOOT};
+
+#[test]
+fn calls_branches_and_halts() {
+    // 0000: call 0x0005
+    // 0003: hlt
+    // 0004: nop ; where an interrupt returns to
+    // 0005: jz 0x0009
+    // 0008: ret
+    // 0009: ret
+    // 000a: db 0xaa ; after the ret
+    let program = vec![0xcd, 0x05, 0x00, 0x76, 0x00, 0xca, 0x09, 0x00, 0xc9, 0xc9, 0xaa];
+    let mem = MemoryImage::rom(program, 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let addrs: Vec<usize> = trace.instructions().map(|(at, _, _)| at.addr).collect();
+    assert_eq!(addrs, [0x0000, 0x0003, 0x0004, 0x0005, 0x0008, 0x0009]);
+    assert_eq!(trace.kind(0x000a), Some(ByteKind::Data));
+
+    let listing = trace.into_listing();
+    assert!(matches!(listing.last(), Some((at, Entry::Data(bytes))) if at.addr == 0x000a && bytes == &[0xaa]));
+}
+
+// 8000: jmp 0x8004
+// 8003: db 0xff
+// 8004: jnz 0x8003 ; into data, which decodes as rst 7
+// 8007: hlt
+// 8008: db 0x00, 0x00
+#[test]
+fn reset_before_vectors() {
+    // 0000: mvi a, 0x01
+    // 0002: out 0x10
+    // 0004: lxi h, 0x2100
+    // 0007: lxi d, 0x3e11 ; rst 1's vector is in the middle of this
+    // 000a: hlt
+    let program = vec![0x3e, 0x01, 0xd3, 0x10, 0x21, 0x00, 0x21, 0x11, 0x11, 0x3e, 0x76];
+    let mem = MemoryImage::rom(program, 0);
+    let trace = Tracer::new(&mem).trace(&ENTRY_POINTS);
+    let addrs: Vec<usize> = trace.instructions().map(|(at, _, _)| at.addr).collect();
+    assert_eq!(addrs, [0x0000, 0x0002, 0x0004, 0x0007, 0x000a]);
+    assert_eq!(trace.kind(0x0008), Some(ByteKind::Operand));
+}
+
+const PROGRAM: &[u8] = &[0xc3, 0x04, 0x80, 0xff, 0xc2, 0x03, 0x80, 0x76, 0x00, 0x00];
+
+#[test]
+fn follows_branches_at_base() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0x8000);
+    let trace = Tracer::new(&mem).trace(&[0x8000]);
+    let addrs: Vec<usize> = trace.instructions().map(|(at, _, _)| at.addr).collect();
+    assert_eq!(addrs, [0x8000, 0x8003, 0x8004, 0x8007, 0x8008, 0x8009]);
+    assert_eq!(trace.kind(0x8001), Some(ByteKind::Operand));
+    assert_eq!(trace.kind(0x0000), None);
+}
+
+#[test]
+fn unreached_bytes_are_data() {
+    // without the jnz, 0x8003 is never reached
+    let mut program = PROGRAM.to_vec();
+    program[4..7].copy_from_slice(&[0xc3, 0x07, 0x80]);
+    let listing = Tracer::new(&MemoryImage::rom(program, 0x8000)).trace(&[0x8000]).into_listing();
+    assert!(matches!(listing[1], (at, Entry::Data(ref bytes)) if at.addr == 0x8003 && bytes == &[0xff]));
+    assert!(matches!(listing[2], (at, Entry::Code(_)) if at.addr == 0x8004));
+}
+
+#[test]
+fn stops_at_unmapped_memory() {
+    // 8000: jmp 0x9000 ; into a second ROM
+    // 8003: lxi h, ... ; running off the end of the first
+    // 9000: jmp 0xa000 ; RAM, which isn't traced into
+    let mut mem = MemoryImage::rom(vec![0xc3, 0x00, 0x90, 0x21, 0x00], 0x8000);
+    mem.map(Region::rom(0x9000, vec![0xc3, 0x00, 0xa0])).unwrap();
+    mem.map(Region::ram(0xa000, 0x100)).unwrap();
+    let trace = Tracer::new(&mem).trace(&[0x8000, 0x8003]);
+
+    let addrs: Vec<usize> = trace.instructions().map(|(at, _, _)| at.addr).collect();
+    assert_eq!(addrs, [0x8000, 0x9000]);
+    assert_eq!(trace.errors(), [DecodeError::Truncated { addr: 0x8003, needed: 3, available: 2 }]);
+    assert_eq!(trace.kind(0x8005), None);
+
+    let listing = trace.into_listing();
+    let addrs: Vec<usize> = listing.iter().map(|(at, _)| at.addr).collect();
+    assert_eq!(addrs, [0x8000, 0x8003, 0x9000]);
+}
+
+/// Code found in `bank`, from a banked trace.
+fn bank_code(sections: &[Section], bank: BankId) -> Vec<usize> {
+    let section = sections.iter().find(|s| s.bank == Some(bank)).unwrap();
+    section.listing.iter().filter(|(_, e)| matches!(e, Entry::Code(_))).map(|(at, _)| at.addr).collect()
+}
+
+#[test]
+fn follows_bank_switches() {
+    // boot, fixed at 0x0000:
+    // 0000: mvi a, 0x01
+    // 0002: out 0x10 ; bank b into 0x4000
+    // 0004: jmp 0x4000
+    // a:
+    // 4000: ret
+    // b:
+    // 4000: pchl
+    let mut file = vec![0xff; 0xc000];
+    file[..7].copy_from_slice(&[0x3e, 0x01, 0xd3, 0x10, 0xc3, 0x00, 0x40]);
+    file[0x4000] = 0xc9;
+    file[0x8000] = 0xe9;
+    let map = two_banks();
+    map.validate().unwrap();
+
+    let sections = Tracer::banked(&map, &file).trace(&[0x0000]).into_sections();
+    assert_eq!(bank_code(&sections, BOOT), [0x0000, 0x0002, 0x0004]);
+    assert_eq!(bank_code(&sections, B), [0x4000]);
+    assert!(sections.iter().all(|s| s.bank != Some(A)));
+
+    // not knowing the selector, 0x4000 might be either bank
+    let sections = Tracer::banked(&map, &file).trace(&[0x4000]).into_sections();
+    assert_eq!(bank_code(&sections, A), [0x4000]);
+    assert_eq!(bank_code(&sections, B), [0x4000]);
+}
+
+#[test]
+fn merges_paths_into_a_shared_switch() {
+    // 0000: mvi a, 0
+    // 0002: jz 0x0007
+    // 0005: mvi a, 1
+    // 0007: out 0x10
+    // 0009: jmp 0x4000
+    // a:
+    // 4000: ret
+    // b:
+    // 4000: ret
+    let mut file = vec![0xff; 0xc000];
+    file[..12].copy_from_slice(&[0x3e, 0x00, 0xca, 0x07, 0x00, 0x3e, 0x01, 0xd3, 0x10, 0xc3, 0x00, 0x40]);
+    file[0x4000] = 0xc9;
+    file[0x8000] = 0xc9;
+    let map = two_banks();
+
+    // the out is reached with either value in A, so both banks get switched in
+    let sections = Tracer::banked(&map, &file).trace(&[0x0000]).into_sections();
+    assert_eq!(bank_code(&sections, BOOT), [0x0000, 0x0002, 0x0005, 0x0007, 0x0009]);
+    assert_eq!(bank_code(&sections, A), [0x4000]);
+    assert_eq!(bank_code(&sections, B), [0x4000]);
+}
+
+#[test]
+fn callees_may_switch_banks() {
+    // 0000: mvi a, 0
+    // 0002: out 0x10
+    // 0004: call 0x0100
+    // 0007: jmp 0x4000
+    // 0100: mvi a, 1
+    // 0102: out 0x10
+    // 0104: ret
+    // a, b:
+    // 4000: ret
+    let mut file = vec![0xff; 0xc000];
+    file[..10].copy_from_slice(&[0x3e, 0x00, 0xd3, 0x10, 0xcd, 0x00, 0x01, 0xc3, 0x00, 0x40]);
+    file[0x100..0x105].copy_from_slice(&[0x3e, 0x01, 0xd3, 0x10, 0xc9]);
+    file[0x4000] = 0xc9;
+    file[0x8000] = 0xc9;
+    let map = two_banks();
+
+    // b is switched in by the time of the jmp
+    let sections = Tracer::banked(&map, &file).trace(&[0x0000]).into_sections();
+    assert_eq!(bank_code(&sections, B), [0x4000]);
+}
+
+#[test]
+fn banked_addresses() {
+    let mut file = vec![0xff; 0xc000];
+    // a: 4000: jmp 0x4003 / 4003: ret; b: 4000: ret
+    file[0x4000..0x4004].copy_from_slice(&[0xc3, 0x03, 0x40, 0xc9]);
+    file[0x8000] = 0xc9;
+    let map = two_banks();
+
+    let trace = Tracer::banked(&map, &file).trace(&[0x4000]);
+    assert_eq!(trace.kind(BankedAddress::new(Some(A), 0x4003)), Some(ByteKind::Opcode));
+    assert_eq!(trace.kind(BankedAddress::new(Some(B), 0x4003)), Some(ByteKind::Data));
+    assert_eq!(map.file_offset(BankedAddress::new(Some(B), 0x4003)), Some(0x8003));
+    assert_eq!(BankedAddress::new(Some(B), 0x4003).to_string(), "02:4003");
+
+    let listing = trace.into_listing();
+    let labels = generate_labels(&listing);
+    assert_eq!(labels.name(BankedAddress::new(Some(A), 0x4003)), Some("loc_01_4003"));
+    assert_eq!(labels.name(BankedAddress::new(Some(B), 0x4003)), None);
+}
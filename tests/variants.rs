@@ -0,0 +1,21 @@
+use ripntear::i8085::{Cpu, DecodeError, Instruction};
+
+fn decode(bytes: &[u8], cpu: Cpu) -> Result<Instruction, DecodeError> {
+    Instruction::decode(bytes, 0, cpu).map(|(_, instr)| instr)
+}
+
+#[test]
+fn i8080_aliases() {
+    assert_eq!(decode(&[0x20], Cpu::I8080), Ok(Instruction::Nop));
+    assert_eq!(decode(&[0xcb, 0x37, 0x13], Cpu::I8080), Ok(Instruction::Jmp { addr: 0x1337, condition: None }));
+    assert_eq!(decode(&[0xd9], Cpu::I8080), Ok(Instruction::Ret { condition: None }));
+    assert_eq!(decode(&[0xfd, 0x37, 0x13], Cpu::I8080), Ok(Instruction::Call { addr: 0x1337, condition: None }));
+    assert_eq!(decode(&[0xdd, 0x37], Cpu::I8080), Err(DecodeError::Truncated { addr: 0, needed: 3, available: 2 }));
+}
+
+#[test]
+fn i8085_documented_only() {
+    assert_eq!(decode(&[0x20], Cpu::I8085), Ok(Instruction::Rim));
+    assert_eq!(decode(&[0x08], Cpu::I8085), Err(DecodeError::UnknownOpcode { addr: 0, opcode: 0x08 }));
+    assert_eq!(decode(&[0x08], Cpu::I8085Undoc), Ok(Instruction::Dsub));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use ripntear::i8085::Tracer;
+use ripntear::{BankedAddress, MemoryImage, Symbols, Xref, XrefKind};
+
+use common::{two_banks, A, B, BOOT};
+
+// 0000: lxi d, 0xf800
+// 0003: ldax d
+// 0004: inx d
+// 0005: stax d
+// 0006: sta 0xf900
+// 0009: lhld 0xf902
+// 000c: in 0x10
+// 000e: out 0x11
+// 0010: call 0x0016
+// 0013: jmp 0x0013
+// 0016: lxi b, 0x1234
+// 0019: mvi b, 0x00
+// 001b: stax b
+// 001c: ret
+const PROGRAM: &[u8] = &[
+    0x11, 0x00, 0xf8, 0x1a, 0x13, 0x12, 0x32, 0x00, 0xf9, 0x2a, 0x02, 0xf9,
+    0xdb, 0x10, 0xd3, 0x11, 0xcd, 0x16, 0x00, 0xc3, 0x13, 0x00, 0x01, 0x34, 0x12,
+    0x06, 0x00, 0x02, 0xc9,
+];
+
+fn xref(to: usize, from: usize, kind: XrefKind) -> Xref {
+    Xref { to: to.into(), from: from.into(), kind }
+}
+
+#[test]
+fn data_and_code() {
+    let mem = MemoryImage::rom(PROGRAM.to_vec(), 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    let xrefs = trace.xrefs();
+
+    let to = |addr| xrefs.to(addr).copied().collect::<Vec<Xref>>();
+    assert_eq!(to(0xf800), [xref(0xf800, 0x00, XrefKind::Pointer), xref(0xf800, 0x03, XrefKind::Read)]);
+    assert_eq!(to(0xf801), [xref(0xf801, 0x05, XrefKind::Write)]);
+    assert_eq!(to(0xf900), [xref(0xf900, 0x06, XrefKind::Write)]);
+    assert_eq!(to(0xf902), [xref(0xf902, 0x09, XrefKind::Read)]);
+    assert_eq!(to(0x16), [xref(0x16, 0x10, XrefKind::Call)]);
+    assert_eq!(to(0x13), [xref(0x13, 0x13, XrefKind::Jump)]);
+
+    assert_eq!(xrefs.to_port(0x10).map(|x| x.kind).collect::<Vec<_>>(), [XrefKind::In]);
+    assert_eq!(xrefs.to_port(0x11).map(|x| x.kind).collect::<Vec<_>>(), [XrefKind::Out]);
+    // ports aren't memory
+    assert_eq!(to(0x10), []);
+
+    // b was overwritten, so bc isn't known by the stax
+    assert!(xrefs.from(0x1b).next().is_none());
+    assert_eq!(to(0x1234), [xref(0x1234, 0x16, XrefKind::Pointer)]);
+    assert_eq!(xrefs.from(BankedAddress::from(0x03)).count(), 1);
+}
+
+#[test]
+fn banked_targets() {
+    // 0000: call 0x4000
+    // 0003: mvi a, 1
+    // 0005: out 0x10
+    // 0007: jmp 0x4000
+    // a, b:
+    // 4000: ret
+    let mut file = vec![0xff; 0xc000];
+    file[..10].copy_from_slice(&[0xcd, 0x00, 0x40, 0x3e, 0x01, 0xd3, 0x10, 0xc3, 0x00, 0x40]);
+    file[0x4000] = 0xc9;
+    file[0x8000] = 0xc9;
+    let map = two_banks();
+    let trace = Tracer::banked(&map, &file).trace(&[0x0000]);
+    let xrefs = trace.xrefs();
+    let from = |at| xrefs.to(BankedAddress::new(Some(at), 0x4000)).map(|x| x.from.addr).collect::<Vec<_>>();
+
+    // before the out, the call might reach either bank; the jmp only reaches b
+    assert_eq!(from(A), [0x0000]);
+    assert_eq!(from(B), [0x0000, 0x0007]);
+    assert_eq!(xrefs.to(0x4000).count(), 0);
+    let comment = xrefs.comment(BankedAddress::new(Some(A), 0x4000), &Symbols::default());
+    assert_eq!(comment.as_deref(), Some("XREF: 00:0000 (call)"));
+    assert_eq!(xrefs.to_port(0x10).map(|x| x.from).collect::<Vec<_>>(), [BankedAddress::new(Some(BOOT), 0x0005)]);
+}
+
+#[test]
+fn callees_may_change_pointers() {
+    // 0000: lxi d, 0xf800
+    // 0003: call 0x0010
+    // 0006: ldax d
+    // 0007: hlt
+    // 0010: lxi d, 0x1234
+    // 0013: ret
+    let mut program = vec![0x11, 0x00, 0xf8, 0xcd, 0x10, 0x00, 0x1a, 0x76];
+    program.resize(0x10, 0x00);
+    program.extend([0x11, 0x34, 0x12, 0xc9]);
+    let mem = MemoryImage::rom(program, 0);
+    let trace = Tracer::new(&mem).trace(&[0]);
+    assert_eq!(trace.xrefs().from(0x06).count(), 0);
+    assert_eq!(trace.xrefs().to(0xf800).map(|x| x.kind).collect::<Vec<_>>(), [XrefKind::Pointer]);
+}